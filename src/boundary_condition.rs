@@ -0,0 +1,307 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::LengthUnit;
+use crate::quantity::Quantity;
+
+///Boundary conditions which can be attached to surface, curve and point groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BcKind {
+    Fixed,    //displacement per DOF, empty DOFs are free
+    Pressure, //normal to the surfaces, positive pushes into the body
+    Traction, //stress vector on the surfaces
+    Force,    //total force, shared by the nodes of the group
+    Temperature,
+    Convection, //heat exchange with the ambient temperature
+    HeatFlux,   //positive into the body
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BcParam {
+    pub name: &'static str, //eg. "Ux"
+    pub quantity: Quantity,
+    pub required: bool, //the others can stay empty, but one of them must be set
+}
+
+const fn param(name: &'static str, quantity: Quantity, required: bool) -> BcParam {
+    BcParam {
+        name,
+        quantity,
+        required,
+    }
+}
+
+const FIXED_PARAMS: &[BcParam] = &[
+    param("Ux", Quantity::Length, false),
+    param("Uy", Quantity::Length, false),
+    param("Uz", Quantity::Length, false),
+    param("Rx", Quantity::Angle, false),
+    param("Ry", Quantity::Angle, false),
+    param("Rz", Quantity::Angle, false),
+];
+
+const PRESSURE_PARAMS: &[BcParam] = &[param("P", Quantity::Stress, true)];
+
+const TRACTION_PARAMS: &[BcParam] = &[
+    param("Tx", Quantity::Stress, false),
+    param("Ty", Quantity::Stress, false),
+    param("Tz", Quantity::Stress, false),
+];
+
+const FORCE_PARAMS: &[BcParam] = &[
+    param("Fx", Quantity::Force, false),
+    param("Fy", Quantity::Force, false),
+    param("Fz", Quantity::Force, false),
+];
+
+const TEMPERATURE_PARAMS: &[BcParam] = &[param("T", Quantity::Temperature, true)];
+
+const CONVECTION_PARAMS: &[BcParam] = &[
+    param("h", Quantity::HeatTransfer, true),
+    param("Tinf", Quantity::Temperature, true),
+];
+
+const HEAT_FLUX_PARAMS: &[BcParam] = &[param("q", Quantity::HeatFlux, true)];
+
+impl BcKind {
+    pub const ALL: [BcKind; 7] = [
+        BcKind::Fixed,
+        BcKind::Pressure,
+        BcKind::Traction,
+        BcKind::Force,
+        BcKind::Temperature,
+        BcKind::Convection,
+        BcKind::HeatFlux,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BcKind::Fixed => "Fixed",
+            BcKind::Pressure => "Pressure",
+            BcKind::Traction => "Traction",
+            BcKind::Force => "Force",
+            BcKind::Temperature => "Temperature",
+            BcKind::Convection => "Convection",
+            BcKind::HeatFlux => "HeatFlux",
+        }
+    }
+
+    ///for the type picker
+    pub fn description(self) -> &'static str {
+        match self {
+            BcKind::Fixed => "Displacement per DOF, 0 to fix, empty to leave free",
+            BcKind::Pressure => "Normal pressure, positive pushes into the body",
+            BcKind::Traction => "Stress vector on the surfaces",
+            BcKind::Force => "Total force on the group",
+            BcKind::Temperature => "Prescribed temperature",
+            BcKind::Convection => "Heat exchange with the ambient temperature Tinf",
+            BcKind::HeatFlux => "Heat flux, positive into the body",
+        }
+    }
+
+    ///parameters in the order they are shown
+    pub fn params(self) -> &'static [BcParam] {
+        match self {
+            BcKind::Fixed => FIXED_PARAMS,
+            BcKind::Pressure => PRESSURE_PARAMS,
+            BcKind::Traction => TRACTION_PARAMS,
+            BcKind::Force => FORCE_PARAMS,
+            BcKind::Temperature => TEMPERATURE_PARAMS,
+            BcKind::Convection => CONVECTION_PARAMS,
+            BcKind::HeatFlux => HEAT_FLUX_PARAMS,
+        }
+    }
+
+    ///distributed loads need surfaces, or curves as the edges of shells in a 2D model
+    ///mesh_dimension is None while the dimension of an automatic model is not known
+    pub fn check_dim(self, dim: u8, mesh_dimension: Option<u8>) -> Result<(), String> {
+        match self {
+            BcKind::Pressure | BcKind::Traction | BcKind::Convection | BcKind::HeatFlux => {
+                match dim {
+                    0 => Err(format!("{} can not be applied to points", self)),
+                    1 if mesh_dimension == Some(3) => Err(format!(
+                        "{} on curves needs a 2D model, apply it to the surfaces",
+                        self
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            BcKind::Fixed | BcKind::Force | BcKind::Temperature => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for BcKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for BcKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BcKind::ALL
+            .into_iter()
+            .find(|kind| kind.label().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("\"{}\" is not a boundary condition type", s.trim()))
+    }
+}
+
+///One boundary condition of a group, values are kept as typed, with or without unit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoundaryCondition {
+    pub kind: BcKind,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>, //parameter name to value, see BcKind::params
+}
+
+impl BoundaryCondition {
+    pub fn new(kind: BcKind) -> Self {
+        BoundaryCondition {
+            kind,
+            params: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> &str {
+        self.params.get(name).map_or("", String::as_str)
+    }
+
+    ///an empty value leaves the parameter unset
+    pub fn set(&mut self, name: &str, value: String) {
+        if value.is_empty() {
+            self.params.remove(name);
+        } else {
+            self.params.insert(name.to_string(), value);
+        }
+    }
+
+    ///"Ux=0 Uy=0", for tables
+    pub fn summary(&self) -> String {
+        let params: Vec<String> = self
+            .kind
+            .params()
+            .iter()
+            .filter(|param| !self.get(param.name).is_empty())
+            .map(|param| format!("{}={}", param.name, self.get(param.name)))
+            .collect();
+        params.join(" ")
+    }
+
+    ///every value must be a number of its quantity, the required ones and at least one set
+    pub fn check(&self, length: LengthUnit) -> Result<(), String> {
+        for param in self.kind.params() {
+            let value = self.get(param.name);
+            if value.is_empty() {
+                if param.required {
+                    return Err(format!("{}: {} is required", self.kind, param.name));
+                }
+                continue;
+            }
+            param
+                .quantity
+                .to_si(value, length)
+                .map_err(|e| format!("{}: {}", param.name, e))?;
+        }
+
+        if self.params.is_empty() {
+            return Err(format!("{}: no value is set", self.kind));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(kind: BcKind, params: &[(&str, &str)]) -> BoundaryCondition {
+        let mut condition = BoundaryCondition::new(kind);
+        for (name, value) in params {
+            condition.set(name, value.to_string());
+        }
+        condition
+    }
+
+    #[test]
+    fn valid_conditions() {
+        let mm = LengthUnit::Mm;
+        assert!(
+            condition(BcKind::Fixed, &[("Ux", "0"), ("Rz", "0")])
+                .check(mm)
+                .is_ok()
+        );
+        assert!(
+            condition(BcKind::Pressure, &[("P", "2 bar")])
+                .check(mm)
+                .is_ok()
+        );
+        assert!(
+            condition(BcKind::Convection, &[("h", "25 W/m2K"), ("Tinf", "20 C")])
+                .check(mm)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn missing_values() {
+        let mm = LengthUnit::Mm;
+        assert_eq!(
+            condition(BcKind::Pressure, &[]).check(mm).unwrap_err(),
+            "Pressure: P is required"
+        );
+        assert_eq!(
+            condition(BcKind::Convection, &[("h", "25")])
+                .check(mm)
+                .unwrap_err(),
+            "Convection: Tinf is required"
+        );
+        //no required value, but one must be set
+        assert_eq!(
+            condition(BcKind::Fixed, &[]).check(mm).unwrap_err(),
+            "Fixed: no value is set"
+        );
+    }
+
+    #[test]
+    fn wrong_units() {
+        let mm = LengthUnit::Mm;
+        let error = condition(BcKind::Force, &[("Fx", "5 MPa")])
+            .check(mm)
+            .unwrap_err();
+        assert!(
+            error.starts_with("Fx: \"5 MPa\" is not a number with a unit"),
+            "{}",
+            error
+        );
+        assert!(
+            condition(BcKind::Temperature, &[("T", "20 F")])
+                .check(mm)
+                .is_err()
+        );
+        assert!(
+            condition(BcKind::Fixed, &[("Ux", "0 deg")])
+                .check(mm)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn distributed_loads_need_surfaces_or_shell_edges() {
+        assert!(BcKind::Pressure.check_dim(0, Some(2)).is_err());
+        assert!(BcKind::Pressure.check_dim(1, Some(2)).is_ok());
+        assert!(BcKind::HeatFlux.check_dim(1, None).is_ok());
+        assert_eq!(
+            BcKind::Traction.check_dim(1, Some(3)),
+            Err(String::from(
+                "Traction on curves needs a 2D model, apply it to the surfaces"
+            ))
+        );
+        assert!(BcKind::Convection.check_dim(2, Some(3)).is_ok());
+        assert!(BcKind::Force.check_dim(0, Some(3)).is_ok());
+        assert!(BcKind::Force.check_dim(1, Some(3)).is_ok());
+        assert_eq!("heatflux".parse::<BcKind>().unwrap(), BcKind::HeatFlux);
+    }
+}
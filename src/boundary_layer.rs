@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TermshError};
+use crate::id_set::IdSet;
+
+///Quadrangle layers grown from the wall curves into the surface mesh, by a BoundaryLayer field
+///Gmsh builds such fields in 2D only, the layers stay inside the surfaces, so CAD models can have them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoundaryLayer {
+    pub wall_curves: IdSet, //walls besides the curve groups marked as walls
+    pub first_thickness: String,
+    pub growth_ratio: String, //thickness of a layer over the one below it
+    pub layer_count: String,
+}
+
+impl BoundaryLayer {
+    ///Distance of the top of each layer to the wall, None until the parameters are valid
+    pub fn heights(&self) -> Option<Vec<f64>> {
+        let first_thickness = self.first_thickness.parse::<f64>().ok()?;
+        let growth_ratio = self.growth_ratio.parse::<f64>().ok()?;
+        let layer_count = self.layer_count.parse::<u32>().ok()?;
+
+        let mut heights = Vec::new();
+        let mut thickness = first_thickness;
+        let mut height = 0.0;
+        for _ in 0..layer_count {
+            height += thickness;
+            heights.push(height);
+            thickness *= growth_ratio;
+        }
+        Some(heights)
+    }
+
+    ///every parameter must be valid once there are walls
+    pub fn check(&self, walls: &IdSet) -> Result<()> {
+        if walls.is_empty() {
+            return Ok(());
+        }
+
+        let invalid = |what: &str, value: &str, expected: &str| {
+            Err(TermshError::Input(format!(
+                "boundary layer {}: \"{}\" is not valid, expected {}",
+                what, value, expected
+            )))
+        };
+        if !self.first_thickness.parse::<f64>().is_ok_and(|t| t > 0.0) {
+            return invalid(
+                "first thickness",
+                &self.first_thickness,
+                "a positive number",
+            );
+        }
+        if !self.growth_ratio.parse::<f64>().is_ok_and(|r| r > 0.0) {
+            return invalid("growth ratio", &self.growth_ratio, "a positive number");
+        }
+        if !self.layer_count.parse::<u32>().is_ok_and(|n| n > 0) {
+            return invalid("layer count", &self.layer_count, "a positive integer");
+        }
+
+        Ok(())
+    }
+
+    ///.geo lines of the BoundaryLayer field with the given id, its total thickness
+    ///gives the layer count, size_far is the element size away from the walls
+    pub fn script_lines(&self, walls: &IdSet, field_id: u32, size_far: &str) -> Vec<String> {
+        let Some(total) = self.heights().and_then(|heights| heights.last().copied()) else {
+            return Vec::new();
+        };
+        if walls.is_empty() {
+            return Vec::new();
+        }
+
+        let mut lines = vec![
+            format!("Field[{}] = BoundaryLayer;", field_id),
+            format!("Field[{}].CurvesList = {{{}}};", field_id, walls),
+            format!("Field[{}].Size = {};", field_id, self.first_thickness),
+            format!("Field[{}].Ratio = {};", field_id, self.growth_ratio),
+            //hide float noise
+            format!(
+                "Field[{}].Thickness = {};",
+                field_id,
+                (total * 1e9).round() / 1e9
+            ),
+            format!("Field[{}].Quads = 1;", field_id),
+        ];
+        if !size_far.is_empty() {
+            lines.push(format!("Field[{}].SizeFar = {};", field_id, size_far));
+        }
+        lines.push(format!("BoundaryLayer Field = {};", field_id));
+        lines
+    }
+}
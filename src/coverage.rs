@@ -0,0 +1,190 @@
+use crate::entity::EntityList;
+use crate::gmsh_ctl::GmshPara;
+use crate::id_set::IdSet;
+
+///Entities that will be lost or exported twice, because Mesh.SaveAll=0 only saves Physical Objects
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    pub unassigned: Vec<(u8, u32)>, //(dim, tag) in no physical group
+    pub duplicated: Vec<(u8, u32, Vec<String>)>, //(dim, tag, group names) in more than one group
+}
+
+impl CoverageReport {
+    pub fn is_clean(&self) -> bool {
+        self.unassigned.is_empty() && self.duplicated.is_empty()
+    }
+
+    ///one readable line per problem
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for (dim, tag) in &self.unassigned {
+            warnings.push(format!("{} {} has no physical group", dim_name(*dim), tag));
+        }
+
+        for (dim, tag, names) in &self.duplicated {
+            warnings.push(format!(
+                "{} {} is in {}",
+                dim_name(*dim),
+                tag,
+                names.join(", ")
+            ));
+        }
+
+        warnings
+    }
+}
+
+fn dim_name(dim: u8) -> &'static str {
+    match dim {
+        3 => "Volume",
+        2 => "Surface",
+        1 => "Curve",
+        _ => "Point",
+    }
+}
+
+///Compare the physical groups against the entities of the model
+///Surfaces shared by two volumes are interfaces, they are not required to be in a group
+///a shell model only meshes surfaces, so every surface needs a group and volumes none
+pub fn check_coverage(gmsh_para: &GmshPara, entity_list: &EntityList) -> CoverageReport {
+    let vol_groups: Vec<(&str, &IdSet)> = gmsh_para
+        .vol_phy_list
+        .iter()
+        .map(|vol_phy| (vol_phy.name.as_str(), &vol_phy.vol_ids))
+        .collect();
+    let surf_groups: Vec<(&str, &IdSet)> = gmsh_para
+        .surf_phy_list
+        .iter()
+        .map(|surf_phy| (surf_phy.name.as_str(), &surf_phy.surf_ids))
+        .collect();
+
+    let mesh_dimension = gmsh_para.mesh_dimension();
+    let mut report = CoverageReport::default();
+
+    for entity in &entity_list.entities {
+        if entity.dim > mesh_dimension {
+            continue;
+        }
+
+        let groups = if entity.dim == 3 {
+            &vol_groups
+        } else {
+            &surf_groups
+        };
+
+        let names: Vec<String> = groups
+            .iter()
+            .filter(|(_, ids)| ids.contains(entity.tag))
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        match names.len() {
+            0 if entity.dim == mesh_dimension || entity.parents.len() < 2 => {
+                report.unassigned.push((entity.dim, entity.tag))
+            }
+            0 | 1 => {}
+            _ => report.duplicated.push((entity.dim, entity.tag, names)),
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Entity;
+    use crate::gmsh_ctl::{ModelDimension, SurfPhys, VolPhys};
+
+    fn entity(dim: u8, tag: u32, parents: &[u32]) -> Entity {
+        Entity {
+            dim,
+            tag,
+            bbox: [0.0; 6],
+            parents: parents.to_vec(),
+            parts: Vec::new(),
+        }
+    }
+
+    fn vol_phys(name: &str, vol_ids: &str) -> VolPhys {
+        VolPhys {
+            name: String::from(name),
+            phys_id: String::from("1"),
+            vol_ids: vol_ids.parse().unwrap(),
+            mesh_size: String::new(),
+            material: String::new(),
+        }
+    }
+
+    fn surf_phys(name: &str, surf_ids: &str) -> SurfPhys {
+        SurfPhys {
+            name: String::from(name),
+            phys_id: String::from("2"),
+            surf_ids: surf_ids.parse().unwrap(),
+            mesh_size: String::new(),
+            boundary_conditions: Vec::new(),
+            material: String::new(),
+            thickness: String::new(),
+        }
+    }
+
+    //two volumes sharing surface 3, volume 2 is in no group and surface 1 in two
+    fn two_volumes() -> EntityList {
+        EntityList {
+            entities: vec![
+                entity(3, 1, &[]),
+                entity(3, 2, &[]),
+                entity(2, 1, &[1]),
+                entity(2, 2, &[2]),
+                entity(2, 3, &[1, 2]),
+            ],
+            ..EntityList::default()
+        }
+    }
+
+    #[test]
+    fn solid_model_problems() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        gmsh_para.vol_phy_list.push(vol_phys("body", "1"));
+        gmsh_para.surf_phy_list.push(surf_phys("inlet", "1"));
+        gmsh_para.surf_phy_list.push(surf_phys("wall", "1:2"));
+
+        let report = check_coverage(&gmsh_para, &two_volumes());
+        assert_eq!(report.unassigned, vec![(3, 2)]);
+        assert_eq!(
+            report.duplicated,
+            vec![(2, 1, vec![String::from("inlet"), String::from("wall")])]
+        );
+        assert_eq!(
+            report.warnings(),
+            vec![
+                String::from("Volume 2 has no physical group"),
+                String::from("Surface 1 is in inlet, wall"),
+            ]
+        );
+    }
+
+    #[test]
+    fn interface_surfaces_are_exempt() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        gmsh_para.vol_phy_list.push(vol_phys("body", "1:2"));
+        gmsh_para.surf_phy_list.push(surf_phys("outer", "1:2"));
+
+        assert!(check_coverage(&gmsh_para, &two_volumes()).is_clean());
+    }
+
+    #[test]
+    fn shell_model_needs_every_surface() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.model_dimension = ModelDimension::Shell;
+        gmsh_para.surf_phy_list.push(surf_phys("outer", "1:2"));
+
+        //volumes are not meshed, the interface surface is a shell of its own
+        let report = check_coverage(&gmsh_para, &two_volumes());
+        assert_eq!(report.unassigned, vec![(2, 3)]);
+        assert!(report.duplicated.is_empty());
+    }
+}
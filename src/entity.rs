@@ -0,0 +1,332 @@
+use std::fs::read_to_string;
+
+use crate::error::{Result, TermshError};
+use crate::geometry::{part_lines, part_list_name};
+use crate::id_set::IdSet;
+
+///One geometrical entity of the healed model, as reported by Gmsh
+#[derive(Debug, Clone)]
+pub struct Entity {
+    pub dim: u8,
+    pub tag: u32,
+    pub bbox: [f64; 6],    //xmin, ymin, zmin, xmax, ymax, zmax
+    pub parents: Vec<u32>, //volumes bounded by this surface, empty for volumes
+    pub parts: Vec<usize>, //index of the geometry parts a volume or a free surface comes from
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EntityList {
+    pub entities: Vec<Entity>,
+    pub part_bboxes: Vec<[f64; 6]>, //bounding box of each geometry part before healing
+    pub curves: IdSet,              //only the tags, curves and points are not browsed
+    pub points: IdSet,
+}
+
+///.geo lines dumping the bounding box of each part as "PART part_idx xmin ymin zmin xmax ymax zmax"
+///must run before healing, the part lists are no longer valid after BooleanFragments
+pub fn part_query_lines(part_count: usize, output_file: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for part_idx in 0..part_count {
+        let list = part_list_name(part_idx);
+        lines.push(format!("If (#{}() > 0)", list));
+        for line in part_lines(&list, |entities| {
+            format!("query_bb[] = BoundingBox {};", entities)
+        }) {
+            lines.push(format!("  {}", line));
+        }
+        lines.push(format!(
+            "  Printf(\"PART {} %g %g %g %g %g %g\", query_bb[0], query_bb[1], query_bb[2], query_bb[3], query_bb[4], query_bb[5]) >> \"{}\";",
+            part_idx, output_file
+        ));
+        lines.push(String::from("EndIf"));
+    }
+
+    lines
+}
+
+///.geo lines dumping every volume and surface into output_file
+///each entity is one "ENTITY dim tag xmin ymin zmin xmax ymax zmax" line
+///tags are printed with %.0f, %g would switch to an exponent from 1e6 on
+///each surface of a volume is one "PARENT surface_tag volume_tag" line
+///curves and points are one "CURVE tag" or "POINT tag" line
+pub fn query_script_lines(output_file: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    //the loops are guarded, because {0:-1} counts down in Gmsh instead of being empty
+    for (dim, name, list) in [(3, "Volume", "query_vols"), (2, "Surface", "query_surfs")] {
+        lines.push(format!("{}[] = {}{{:}};", list, name));
+        lines.push(format!("If (#{}[] > 0)", list));
+        lines.push(format!("  For query_i In {{0:#{}[]-1}}", list));
+        lines.push(format!(
+            "    query_bb[] = BoundingBox {}{{{}[query_i]}};",
+            name, list
+        ));
+        lines.push(format!(
+            "    Printf(\"ENTITY {} %.0f %g %g %g %g %g %g\", {}[query_i], query_bb[0], query_bb[1], query_bb[2], query_bb[3], query_bb[4], query_bb[5]) >> \"{}\";",
+            dim, list, output_file
+        ));
+        lines.push(String::from("  EndFor"));
+        lines.push(String::from("EndIf"));
+    }
+
+    for (keyword, name, list) in [
+        ("CURVE", "Curve", "query_curves"),
+        ("POINT", "Point", "query_points"),
+    ] {
+        lines.push(format!("{}[] = {}{{:}};", list, name));
+        lines.push(format!("If (#{}[] > 0)", list));
+        lines.push(format!("  For query_i In {{0:#{}[]-1}}", list));
+        lines.push(format!(
+            "    Printf(\"{} %.0f\", {}[query_i]) >> \"{}\";",
+            keyword, list, output_file
+        ));
+        lines.push(String::from("  EndFor"));
+        lines.push(String::from("EndIf"));
+    }
+
+    lines.push(String::from("If (#query_vols[] > 0)"));
+    lines.push(String::from("  For query_i In {0:#query_vols[]-1}"));
+    lines.push(String::from(
+        "    query_bnd[] = Boundary{ Volume{query_vols[query_i]}; };",
+    ));
+    lines.push(String::from("    For query_j In {0:#query_bnd[]-1}"));
+    lines.push(format!(
+        "      Printf(\"PARENT %.0f %.0f\", query_bnd[query_j], query_vols[query_i]) >> \"{}\";",
+        output_file
+    ));
+    lines.push(String::from("    EndFor"));
+    lines.push(String::from("  EndFor"));
+    lines.push(String::from("EndIf"));
+
+    lines
+}
+
+impl EntityList {
+    ///Read the file written by the query_script_lines() script
+    pub fn read_query_output(filename: &str) -> Result<Self> {
+        let content = read_to_string(filename)
+            .map_err(TermshError::io(format!("failed to read {}", filename)))?;
+        let mut entity_list = EntityList::default();
+        let mut parent_pairs = Vec::new();
+
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let numbers: Vec<f64> = fields
+                .iter()
+                .skip(1)
+                .map(|field| field.parse::<f64>())
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| TermshError::Gmsh(format!("unexpected entity line: {}", line)))?;
+
+            match (fields.first(), numbers.as_slice()) {
+                (Some(&"ENTITY"), [dim, tag, bbox @ ..]) if bbox.len() == 6 => {
+                    entity_list.entities.push(Entity {
+                        dim: *dim as u8,
+                        tag: *tag as u32,
+                        bbox: [bbox[0], bbox[1], bbox[2], bbox[3], bbox[4], bbox[5]],
+                        parents: Vec::new(),
+                        parts: Vec::new(),
+                    });
+                }
+                (Some(&"PART"), [part_idx, bbox @ ..]) if bbox.len() == 6 => {
+                    let part_idx = *part_idx as usize;
+                    if entity_list.part_bboxes.len() <= part_idx {
+                        entity_list.part_bboxes.resize(part_idx + 1, [0.0; 6]);
+                    }
+                    entity_list.part_bboxes[part_idx] =
+                        [bbox[0], bbox[1], bbox[2], bbox[3], bbox[4], bbox[5]];
+                }
+                //boundary tags are signed by orientation
+                (Some(&"PARENT"), [surf_tag, vol_tag]) => {
+                    parent_pairs.push((surf_tag.abs() as u32, *vol_tag as u32))
+                }
+                (Some(&"CURVE"), [tag]) => entity_list.curves.insert(*tag as u32),
+                (Some(&"POINT"), [tag]) => entity_list.points.insert(*tag as u32),
+                (None, _) => {} //empty line
+                _ => {
+                    return Err(TermshError::Gmsh(format!(
+                        "unexpected entity line: {}",
+                        line
+                    )));
+                }
+            }
+        }
+
+        for (surf_tag, vol_tag) in parent_pairs {
+            if let Some(surface) = entity_list
+                .entities
+                .iter_mut()
+                .find(|entity| entity.dim == 2 && entity.tag == surf_tag)
+                && !surface.parents.contains(&vol_tag)
+            {
+                surface.parents.push(vol_tag);
+            }
+        }
+
+        //healing splits and renumbers volumes, but each piece stays inside the part it comes from
+        //a piece where parts overlap is inside several of them, surfaces of a shell part bound no volume
+        for entity in entity_list
+            .entities
+            .iter_mut()
+            .filter(|e| e.dim == 3 || e.parents.is_empty())
+        {
+            entity.parts = entity_list
+                .part_bboxes
+                .iter()
+                .enumerate()
+                .filter(|(_, part_bbox)| bbox_contains(part_bbox, &entity.bbox))
+                .map(|(part_idx, _)| part_idx)
+                .collect();
+        }
+
+        entity_list
+            .entities
+            .sort_by_key(|entity| (std::cmp::Reverse(entity.dim), entity.tag));
+        Ok(entity_list)
+    }
+
+    ///2 for a model of surfaces only, eg. thin-walled parts, otherwise 3
+    pub fn dimension(&self) -> u8 {
+        let has_surfaces = self.entities.iter().any(|entity| entity.dim == 2);
+        let has_volumes = self.entities.iter().any(|entity| entity.dim == 3);
+        if has_surfaces && !has_volumes { 2 } else { 3 }
+    }
+
+    pub fn tags(&self, dim: u8) -> IdSet {
+        match dim {
+            1 => self.curves.clone(),
+            0 => self.points.clone(),
+            _ => self
+                .entities
+                .iter()
+                .filter(|entity| entity.dim == dim)
+                .map(|entity| entity.tag)
+                .collect(),
+        }
+    }
+
+    ///ids of the set which are not entities of the given dimension
+    pub fn missing(&self, dim: u8, ids: &IdSet) -> IdSet {
+        let tags = self.tags(dim);
+        ids.iter().filter(|id| !tags.contains(*id)).collect()
+    }
+}
+
+//OCC bounding boxes are slightly loose, so allow a small fraction of the outer box size
+fn bbox_contains(outer: &[f64; 6], inner: &[f64; 6]) -> bool {
+    let diagonal = (0..3)
+        .map(|axis| (outer[axis + 3] - outer[axis]).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    let tolerance = diagonal * 1e-3;
+
+    (0..3).all(|axis| {
+        inner[axis] >= outer[axis] - tolerance && inner[axis + 3] <= outer[axis + 3] + tolerance
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn fixture() -> EntityList {
+        EntityList::read_query_output(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/entity_query.txt"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn query_output_is_read() {
+        let entity_list = fixture();
+        let keys: Vec<(u8, u32)> = entity_list
+            .entities
+            .iter()
+            .map(|e| (e.dim, e.tag))
+            .collect();
+        assert_eq!(keys, [(3, 1), (3, 2), (2, 1), (2, 7), (2, 9)]);
+        assert_eq!(
+            entity_list.entities[1].bbox,
+            [10.0, 0.0, 0.0, 20.0, 10.0, 10.0]
+        );
+        assert_eq!(entity_list.part_bboxes.len(), 2);
+        assert_eq!(entity_list.dimension(), 3);
+
+        //the signed and repeated PARENT lines give one parent per volume
+        assert_eq!(entity_list.entities[3].parents, vec![1, 2]);
+        assert_eq!(entity_list.entities[2].parents, vec![1]);
+    }
+
+    #[test]
+    fn parts_are_assigned_by_bounding_box() {
+        let entity_list = fixture();
+        assert_eq!(entity_list.entities[0].parts, vec![0]);
+        assert_eq!(entity_list.entities[1].parts, vec![1]);
+        //the interface surface touches both parts, but only free surfaces get parts
+        assert!(entity_list.entities[3].parts.is_empty());
+        assert_eq!(entity_list.entities[4].parts, vec![1]);
+    }
+
+    #[test]
+    fn missing_ids() {
+        let entity_list = fixture();
+        let ids: IdSet = "1:3, 7".parse().unwrap();
+        assert_eq!(entity_list.missing(3, &ids).to_string(), "3, 7");
+        assert_eq!(entity_list.missing(2, &ids).to_string(), "2:3");
+        assert_eq!(entity_list.missing(1, &ids).to_string(), "3, 7");
+        assert_eq!(entity_list.missing(0, &ids).to_string(), "3, 7");
+    }
+
+    #[test]
+    fn tags_are_printed_as_integers() {
+        let lines = query_script_lines("query.txt");
+        for line in lines.iter().filter(|line| line.contains("Printf")) {
+            let format = line.split('"').nth(1).unwrap();
+            let keyword = format.split(' ').next().unwrap();
+            let tags = if keyword == "ENTITY" {
+                1
+            } else {
+                format.matches('%').count()
+            };
+            assert_eq!(format.matches("%.0f").count(), tags, "{}", line);
+        }
+
+        //large tags of big assemblies
+        let file = std::env::temp_dir().join(format!("termsh_tags_{}.txt", std::process::id()));
+        fs::write(
+            &file,
+            "ENTITY 3 1000001 0 0 0 1e+06 1 1\nENTITY 2 2500000 0 0 0 1e+06 1 0\nPARENT -2500000 1000001\nCURVE 3000000\n",
+        )
+        .unwrap();
+        let result = EntityList::read_query_output(file.to_str().unwrap());
+        fs::remove_file(&file).unwrap();
+
+        let entity_list = result.unwrap();
+        assert_eq!(entity_list.tags(3).to_string(), "1000001");
+        assert_eq!(entity_list.tags(1).to_string(), "3000000");
+        let surface = entity_list
+            .entities
+            .iter()
+            .find(|entity| entity.dim == 2)
+            .unwrap();
+        assert_eq!(surface.tag, 2500000);
+        assert_eq!(surface.parents, [1000001]);
+        assert_eq!(surface.bbox[3], 1e6);
+    }
+
+    #[test]
+    fn malformed_line_is_rejected() {
+        let file = std::env::temp_dir().join(format!("termsh_entity_{}.txt", std::process::id()));
+        fs::write(&file, "ENTITY 3 1 0 0 0 10 10 10\nENTITY 2 x 0 0 0 1 1 1\n").unwrap();
+        let result = EntityList::read_query_output(file.to_str().unwrap());
+        fs::remove_file(&file).unwrap();
+
+        match result {
+            Err(TermshError::Gmsh(reason)) => assert!(reason.contains("ENTITY 2 x"), "{}", reason),
+            other => panic!("expected a Gmsh error, got {:?}", other),
+        }
+    }
+}
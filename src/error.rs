@@ -0,0 +1,56 @@
+use std::{error, fmt, io};
+
+///Everything that can go wrong in termsh, shown as a popup in the TUI or printed by main
+#[derive(Debug)]
+pub enum TermshError {
+    Io { context: String, source: io::Error }, //a file or process operation failed
+    GmshStart(io::Error),                      //gmsh is not installed or not in PATH
+    Gmsh(String),                              //Gmsh ran but reported a failure
+    Project(String),                           //the project file can not be understood
+    Input(String),                             //what the user asked for can not be done
+    Coverage(usize), //entities in no physical group or in several, batch export exits with 2
+}
+
+pub type Result<T> = std::result::Result<T, TermshError>;
+
+impl TermshError {
+    ///For map_err on io results, eg. .map_err(TermshError::io("failed to write a.geo"))
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> TermshError {
+        let context = context.into();
+        move |source| TermshError::Io { context, source }
+    }
+
+    ///Exit code of the command line, 2 tells a coverage failure apart from the other errors
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TermshError::Coverage(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for TermshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TermshError::Io { context, source } => write!(f, "{}: {}", context, source),
+            TermshError::GmshStart(source) => write!(f, "failed to start gmsh: {}", source),
+            TermshError::Gmsh(reason) => write!(f, "{}", reason),
+            TermshError::Project(reason) => write!(f, "{}", reason),
+            TermshError::Input(reason) => write!(f, "{}", reason),
+            TermshError::Coverage(count) => write!(
+                f,
+                "coverage check failed with {} problem(s), nothing exported",
+                count
+            ),
+        }
+    }
+}
+
+impl error::Error for TermshError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TermshError::Io { source, .. } | TermshError::GmshStart(source) => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::EntityList;
+use crate::id_set::IdSet;
+
+///Gmsh mesh size fields termsh can define, named as in the .geo script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldKind {
+    Distance,  //distance to surfaces, curves or points
+    Threshold, //size from the distance computed by another field
+    Box,
+    Ball,
+    Cylinder,
+    MathEval, //size from a formula of x, y, z
+    Min,      //smallest size of several fields
+}
+
+///What a field parameter holds, decides how it is checked and written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Surfaces,
+    Curves,
+    Points,
+    Number,
+    Integer,
+    Bool,
+    Field,      //id of another field
+    FieldList,  //ids of other fields
+    Expression, //written in quotes
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FieldParam {
+    pub name: &'static str, //Gmsh name, eg. "SizeMin"
+    pub kind: ParamKind,
+}
+
+const fn param(name: &'static str, kind: ParamKind) -> FieldParam {
+    FieldParam { name, kind }
+}
+
+const DISTANCE_PARAMS: &[FieldParam] = &[
+    param("SurfacesList", ParamKind::Surfaces),
+    param("CurvesList", ParamKind::Curves),
+    param("PointsList", ParamKind::Points),
+    param("Sampling", ParamKind::Integer),
+];
+
+const THRESHOLD_PARAMS: &[FieldParam] = &[
+    param("InField", ParamKind::Field),
+    param("SizeMin", ParamKind::Number),
+    param("SizeMax", ParamKind::Number),
+    param("DistMin", ParamKind::Number),
+    param("DistMax", ParamKind::Number),
+    param("Sigmoid", ParamKind::Bool),
+    param("StopAtDistMax", ParamKind::Bool),
+];
+
+const BOX_PARAMS: &[FieldParam] = &[
+    param("VIn", ParamKind::Number),
+    param("VOut", ParamKind::Number),
+    param("XMin", ParamKind::Number),
+    param("XMax", ParamKind::Number),
+    param("YMin", ParamKind::Number),
+    param("YMax", ParamKind::Number),
+    param("ZMin", ParamKind::Number),
+    param("ZMax", ParamKind::Number),
+    param("Thickness", ParamKind::Number),
+];
+
+const BALL_PARAMS: &[FieldParam] = &[
+    param("VIn", ParamKind::Number),
+    param("VOut", ParamKind::Number),
+    param("XCenter", ParamKind::Number),
+    param("YCenter", ParamKind::Number),
+    param("ZCenter", ParamKind::Number),
+    param("Radius", ParamKind::Number),
+    param("Thickness", ParamKind::Number),
+];
+
+const CYLINDER_PARAMS: &[FieldParam] = &[
+    param("VIn", ParamKind::Number),
+    param("VOut", ParamKind::Number),
+    param("XCenter", ParamKind::Number),
+    param("YCenter", ParamKind::Number),
+    param("ZCenter", ParamKind::Number),
+    param("XAxis", ParamKind::Number),
+    param("YAxis", ParamKind::Number),
+    param("ZAxis", ParamKind::Number),
+    param("Radius", ParamKind::Number),
+];
+
+const MATH_EVAL_PARAMS: &[FieldParam] = &[param("F", ParamKind::Expression)];
+
+const MIN_PARAMS: &[FieldParam] = &[param("FieldsList", ParamKind::FieldList)];
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 7] = [
+        FieldKind::Distance,
+        FieldKind::Threshold,
+        FieldKind::Box,
+        FieldKind::Ball,
+        FieldKind::Cylinder,
+        FieldKind::MathEval,
+        FieldKind::Min,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FieldKind::Distance => "Distance",
+            FieldKind::Threshold => "Threshold",
+            FieldKind::Box => "Box",
+            FieldKind::Ball => "Ball",
+            FieldKind::Cylinder => "Cylinder",
+            FieldKind::MathEval => "MathEval",
+            FieldKind::Min => "Min",
+        }
+    }
+
+    ///for the type picker
+    pub fn description(self) -> &'static str {
+        match self {
+            FieldKind::Distance => "Distance to surfaces, curves or points",
+            FieldKind::Threshold => "Size growing with the distance of a Distance field",
+            FieldKind::Box => "VIn inside a box, VOut outside",
+            FieldKind::Ball => "VIn inside a ball, VOut outside",
+            FieldKind::Cylinder => "VIn inside a cylinder, VOut outside",
+            FieldKind::MathEval => "Size from a formula of x, y and z",
+            FieldKind::Min => "Smallest size of several fields",
+        }
+    }
+
+    ///parameters in the order they are shown and written
+    pub fn params(self) -> &'static [FieldParam] {
+        match self {
+            FieldKind::Distance => DISTANCE_PARAMS,
+            FieldKind::Threshold => THRESHOLD_PARAMS,
+            FieldKind::Box => BOX_PARAMS,
+            FieldKind::Ball => BALL_PARAMS,
+            FieldKind::Cylinder => CYLINDER_PARAMS,
+            FieldKind::MathEval => MATH_EVAL_PARAMS,
+            FieldKind::Min => MIN_PARAMS,
+        }
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for FieldKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FieldKind::ALL
+            .into_iter()
+            .find(|kind| kind.label().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("\"{}\" is not a field type", s.trim()))
+    }
+}
+
+impl FieldParam {
+    ///Check the format of a value, an empty value keeps the Gmsh default
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        let valid = match self.kind {
+            ParamKind::Surfaces | ParamKind::Curves | ParamKind::Points | ParamKind::FieldList => {
+                value.parse::<IdSet>().is_ok()
+            }
+            ParamKind::Number => value.parse::<f64>().is_ok(),
+            ParamKind::Integer => value.parse::<u32>().is_ok_and(|value| value > 0),
+            ParamKind::Field => value.parse::<u32>().is_ok(),
+            ParamKind::Bool => value == "0" || value == "1",
+            ParamKind::Expression => !value.contains('"'),
+        };
+
+        if valid {
+            return Ok(());
+        }
+
+        let expected = match self.kind {
+            ParamKind::Surfaces | ParamKind::Curves | ParamKind::Points => "a list of ids",
+            ParamKind::FieldList => "a list of field ids",
+            ParamKind::Number => "a number",
+            ParamKind::Integer => "a positive integer",
+            ParamKind::Field => "a field id",
+            ParamKind::Bool => "0 or 1",
+            ParamKind::Expression => "a formula without quotes",
+        };
+        Err(format!(
+            "{}: \"{}\" is not valid, expected {}",
+            self.name, value, expected
+        ))
+    }
+
+    //value as written after "Field[id].Name = "
+    //lists are normalized, "1-4" would be a subtraction and "1 2" a syntax error in Gmsh
+    fn script_value(&self, value: &str) -> String {
+        match self.kind {
+            ParamKind::Surfaces | ParamKind::Curves | ParamKind::Points | ParamKind::FieldList => {
+                match value.parse::<IdSet>() {
+                    Ok(ids) => format!("{{{}}}", ids),
+                    Err(_) => format!("{{{}}}", value), //refused by validate before
+                }
+            }
+            ParamKind::Expression => format!("\"{}\"", value),
+            _ => value.to_string(),
+        }
+    }
+
+    //field ids this parameter points to
+    fn field_refs(&self, value: &str) -> Vec<u32> {
+        match self.kind {
+            ParamKind::Field => value.parse::<u32>().into_iter().collect(),
+            ParamKind::FieldList => value
+                .parse::<IdSet>()
+                .map(|ids| ids.iter().collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+///One Field[id] of the script, parameters not set keep the Gmsh default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshField {
+    pub id: u32,
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>, //Gmsh parameter name to value, see FieldKind::params
+}
+
+impl MeshField {
+    pub fn new(id: u32, kind: FieldKind) -> Self {
+        MeshField {
+            id,
+            kind,
+            params: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> &str {
+        self.params.get(name).map_or("", String::as_str)
+    }
+
+    ///an empty value goes back to the Gmsh default
+    pub fn set(&mut self, name: &str, value: String) {
+        if value.is_empty() {
+            self.params.remove(name);
+        } else {
+            self.params.insert(name.to_string(), value);
+        }
+    }
+
+    ///"SizeMin=1 SizeMax=5", for tables
+    pub fn summary(&self) -> String {
+        let params: Vec<String> = self
+            .kind
+            .params()
+            .iter()
+            .filter(|param| !self.get(param.name).is_empty())
+            .map(|param| format!("{}={}", param.name, self.get(param.name)))
+            .collect();
+        params.join(" ")
+    }
+
+    pub fn script_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Field[{}] = {};", self.id, self.kind)];
+        for param in self.kind.params() {
+            let value = self.get(param.name);
+            if !value.is_empty() {
+                lines.push(format!(
+                    "Field[{}].{} = {};",
+                    self.id,
+                    param.name,
+                    param.script_value(value)
+                ));
+            }
+        }
+        lines
+    }
+
+    ///ids of other fields this one is computed from
+    pub fn field_refs(&self) -> Vec<u32> {
+        self.kind
+            .params()
+            .iter()
+            .flat_map(|param| param.field_refs(self.get(param.name)))
+            .collect()
+    }
+
+    ///Invalid values, eg. from an edited project file, and fields and entities which are
+    ///referenced but do not exist, entities are only checked once they are known
+    pub fn problems(&self, fields: &[MeshField], entity_list: Option<&EntityList>) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .kind
+            .params()
+            .iter()
+            .filter_map(|param| param.validate(self.get(param.name)).err())
+            .map(|e| format!("Field {} {}", self.id, e))
+            .collect();
+
+        for field_id in self.field_refs() {
+            if field_id == self.id {
+                problems.push(format!("Field {} refers to itself", self.id));
+            } else if !fields.iter().any(|field| field.id == field_id) {
+                problems.push(format!(
+                    "Field {} refers to field {} which does not exist",
+                    self.id, field_id
+                ));
+            }
+        }
+
+        if let Some(entity_list) = entity_list {
+            for (name, dim, entities) in [
+                ("SurfacesList", 2, "surfaces"),
+                ("CurvesList", 1, "curves"),
+                ("PointsList", 0, "points"),
+            ] {
+                let Ok(ids) = self.get(name).parse::<IdSet>() else {
+                    continue;
+                };
+                let missing = entity_list.missing(dim, &ids);
+                if !missing.is_empty() {
+                    problems.push(format!(
+                        "Field {} refers to missing {} {}",
+                        self.id, entities, missing
+                    ));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+///Every problem of the fields and of the background field choice
+pub fn field_problems(
+    fields: &[MeshField],
+    background_field: Option<u32>,
+    entity_list: Option<&EntityList>,
+) -> Vec<String> {
+    let mut problems: Vec<String> = fields
+        .iter()
+        .flat_map(|field| field.problems(fields, entity_list))
+        .collect();
+
+    if let Some(background_field) = background_field
+        && !fields.iter().any(|field| field.id == background_field)
+    {
+        problems.push(format!(
+            "background field {} does not exist",
+            background_field
+        ));
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(id: u32, kind: FieldKind, params: &[(&str, &str)]) -> MeshField {
+        let mut field = MeshField::new(id, kind);
+        for (name, value) in params {
+            field.set(name, value.to_string());
+        }
+        field
+    }
+
+    #[test]
+    fn values_are_validated_by_kind() {
+        let [surfaces, _, _, sampling] = DISTANCE_PARAMS else {
+            unreachable!()
+        };
+        assert!(surfaces.validate("1-4, 7").is_ok());
+        assert!(surfaces.validate("").is_ok());
+        assert!(surfaces.validate("1 x").is_err());
+        assert!(sampling.validate("20").is_ok());
+        assert_eq!(
+            sampling.validate("0"),
+            Err(String::from(
+                "Sampling: \"0\" is not valid, expected a positive integer"
+            ))
+        );
+        assert!(sampling.validate("2.5").is_err());
+        assert!(THRESHOLD_PARAMS[5].validate("2").is_err());
+        assert!(MATH_EVAL_PARAMS[0].validate("\"x\"").is_err());
+    }
+
+    #[test]
+    fn lists_are_written_normalized() {
+        let distance = field(
+            1,
+            FieldKind::Distance,
+            &[
+                ("SurfacesList", "1-4"),
+                ("CurvesList", "3 2 1"),
+                ("Sampling", "20"),
+            ],
+        );
+        assert_eq!(
+            distance.script_lines(),
+            [
+                "Field[1] = Distance;",
+                "Field[1].SurfacesList = {1:4};",
+                "Field[1].CurvesList = {1:3};",
+                "Field[1].Sampling = 20;",
+            ]
+        );
+
+        let math_eval = field(2, FieldKind::MathEval, &[("F", "0.1 + x/10")]);
+        assert_eq!(math_eval.script_lines()[1], "Field[2].F = \"0.1 + x/10\";");
+    }
+
+    #[test]
+    fn missing_references() {
+        let entity_list = EntityList {
+            curves: "1:4".parse().unwrap(),
+            points: "1:8".parse().unwrap(),
+            ..EntityList::default()
+        };
+        let fields = vec![
+            field(
+                1,
+                FieldKind::Distance,
+                &[
+                    ("SurfacesList", "2"),
+                    ("CurvesList", "4:5"),
+                    ("PointsList", "8"),
+                    ("Sampling", "0"),
+                ],
+            ),
+            field(2, FieldKind::Min, &[("FieldsList", "1, 4")]),
+            field(3, FieldKind::Threshold, &[("InField", "3")]),
+        ];
+
+        //entities are not checked before they are queried
+        assert_eq!(
+            field_problems(&fields, Some(4), None),
+            [
+                "Field 1 Sampling: \"0\" is not valid, expected a positive integer",
+                "Field 2 refers to field 4 which does not exist",
+                "Field 3 refers to itself",
+                "background field 4 does not exist",
+            ]
+        );
+        assert_eq!(
+            fields[0].problems(&fields, Some(&entity_list))[1..],
+            [
+                "Field 1 refers to missing surfaces 2",
+                "Field 1 refers to missing curves 5",
+            ]
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TermshError};
+
+///Geometry files Gmsh can read, detected from the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryFormat {
+    Step,
+    Iges,
+    Brep,
+    Geo, //native Gmsh script, run as it is
+}
+
+///for messages, eg. when an unknown file is given
+pub const SUPPORTED_EXTENSIONS: &str = ".step/.stp, .iges/.igs, .brep or .geo";
+
+impl GeometryFormat {
+    ///Case-insensitive, so part.STEP and part.Stp are found as well
+    pub fn detect(filename: &str) -> Option<Self> {
+        let extension = Path::new(filename)
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "step" | "stp" => Some(GeometryFormat::Step),
+            "iges" | "igs" => Some(GeometryFormat::Iges),
+            "brep" => Some(GeometryFormat::Brep),
+            "geo" => Some(GeometryFormat::Geo),
+            _ => None,
+        }
+    }
+
+    ///CAD files are imported through OpenCASCADE and need healing, a .geo defines its own model
+    pub fn is_cad(self) -> bool {
+        self != GeometryFormat::Geo
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    M,
+    Cm,
+    Mm,
+    Um,
+    In,
+    Ft,
+}
+
+impl LengthUnit {
+    pub const ALL: [LengthUnit; 6] = [
+        LengthUnit::M,
+        LengthUnit::Cm,
+        LengthUnit::Mm,
+        LengthUnit::Um,
+        LengthUnit::In,
+        LengthUnit::Ft,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LengthUnit::M => "m",
+            LengthUnit::Cm => "cm",
+            LengthUnit::Mm => "mm",
+            LengthUnit::Um => "um",
+            LengthUnit::In => "in",
+            LengthUnit::Ft => "ft",
+        }
+    }
+
+    pub fn in_meters(self) -> f64 {
+        match self {
+            LengthUnit::M => 1.0,
+            LengthUnit::Cm => 1e-2,
+            LengthUnit::Mm => 1e-3,
+            LengthUnit::Um => 1e-6,
+            LengthUnit::In => 0.0254,
+            LengthUnit::Ft => 0.3048,
+        }
+    }
+
+    //name of the unit for Geometry.OCCTargetUnit
+    fn occ_name(self) -> &'static str {
+        match self {
+            LengthUnit::M => "M",
+            LengthUnit::Cm => "CM",
+            LengthUnit::Mm => "MM",
+            LengthUnit::Um => "UM",
+            LengthUnit::In => "INCH",
+            LengthUnit::Ft => "FT",
+        }
+    }
+}
+
+impl fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for LengthUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        LengthUnit::ALL
+            .into_iter()
+            .find(|unit| unit.label() == s)
+            .ok_or_else(|| {
+                let labels: Vec<&str> = LengthUnit::ALL.iter().map(|unit| unit.label()).collect();
+                format!("\"{}\" is not a unit, expected {}", s, labels.join("/"))
+            })
+    }
+}
+
+///STEP and IGES files store their unit and OpenCASCADE converts them to mm unless told otherwise
+///BREP and .geo files have no unit, their coordinates are taken as the source unit
+///the model, and so every size and translation typed in termsh, is in the target unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Units {
+    pub source: LengthUnit,
+    pub target: LengthUnit,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units {
+            source: LengthUnit::Mm,
+            target: LengthUnit::Mm,
+        }
+    }
+}
+
+impl Units {
+    //factor bringing coordinates without unit into the target unit
+    fn unitless_factor(self) -> f64 {
+        self.source.in_meters() / self.target.in_meters()
+    }
+
+    ///.geo lines to set before a STEP or IGES file is imported
+    pub fn option_lines(self) -> Vec<String> {
+        if self.target == LengthUnit::Mm {
+            return Vec::new(); //already what OpenCASCADE does
+        }
+        vec![format!(
+            "Geometry.OCCTargetUnit=\"{}\";",
+            self.target.occ_name()
+        )]
+    }
+}
+
+///One geometry file of the model, scaled around the origin first and then translated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeometryPart {
+    pub file: String,
+    #[serde(default = "unit_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub translation: [f64; 3],
+}
+
+fn unit_scale() -> f64 {
+    1.0
+}
+
+impl GeometryPart {
+    pub fn new(file: String) -> Self {
+        GeometryPart {
+            file,
+            scale: 1.0,
+            translation: [0.0; 3],
+        }
+    }
+
+    ///Unknown extensions are rejected by validate_parts(), they are treated as STEP here
+    pub fn format(&self) -> GeometryFormat {
+        GeometryFormat::detect(&self.file).unwrap_or(GeometryFormat::Step)
+    }
+
+    ///file name without folders, for tables
+    pub fn name(&self) -> String {
+        Path::new(&self.file)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.file.clone())
+    }
+
+    pub fn is_moved(&self) -> bool {
+        self.scale != 1.0 || self.translation != [0.0; 3]
+    }
+
+    //the entities of the part are in the list right after ShapeFromFile
+    //unit_factor converts the file into the target unit, the translation is in the target unit
+    fn placement_lines(&self, list: &str, unit_factor: f64) -> Vec<String> {
+        let mut lines = Vec::new();
+        let scale = self.scale * unit_factor;
+        if scale != 1.0 {
+            lines.extend(part_lines(list, |entities| {
+                format!("Dilate {{{{0, 0, 0}}, {}}} {{ {}; }}", scale, entities)
+            }));
+        }
+        if self.translation != [0.0; 3] {
+            let [x, y, z] = self.translation;
+            lines.extend(part_lines(list, |entities| {
+                format!("Translate {{{}, {}, {}}} {{ {}; }}", x, y, z, entities)
+            }));
+        }
+        lines
+    }
+}
+
+///.geo list holding the entities of a part until healing fuses them
+///ShapeFromFile returns the highest dimension found, the volumes or the surfaces of a shell part
+pub fn part_list_name(part_idx: usize) -> String {
+    format!("part_{}", part_idx + 1)
+}
+
+///.geo lines running a command on the entities of a part, eg. "Volume{part_1()}"
+///the "_solid" flag written on import tells volumes from surfaces
+pub fn part_lines(list: &str, command: impl Fn(&str) -> String) -> Vec<String> {
+    vec![
+        format!("If ({}_solid)", list),
+        format!("  {}", command(&format!("Volume{{{}()}}", list))),
+        String::from("Else"),
+        format!("  {}", command(&format!("Surface{{{}()}}", list))),
+        String::from("EndIf"),
+    ]
+}
+
+pub fn is_cad_model(parts: &[GeometryPart]) -> bool {
+    parts.iter().all(|part| part.format().is_cad())
+}
+
+///.geo lines loading every part into one model, in the target unit
+pub fn import_lines(parts: &[GeometryPart], units: Units) -> Vec<String> {
+    if !is_cad_model(parts) {
+        //a .geo script is the only part, it chooses its own kernel
+        let mut lines: Vec<String> = parts
+            .iter()
+            .map(|part| format!("Merge \"{}\";", part.file))
+            .collect();
+        if units.unitless_factor() != 1.0 {
+            //a model of surfaces only is scaled through its surfaces
+            lines.push(String::from("import_volumes[] = Volume{:};"));
+            lines.push(String::from("If (#import_volumes[] > 0)"));
+            lines.push(format!(
+                "  Dilate {{{{0, 0, 0}}, {}}} {{ Volume{{:}}; }}",
+                units.unitless_factor()
+            ));
+            lines.push(String::from("Else"));
+            lines.push(format!(
+                "  Dilate {{{{0, 0, 0}}, {}}} {{ Surface{{:}}; }}",
+                units.unitless_factor()
+            ));
+            lines.push(String::from("EndIf"));
+        }
+        return lines;
+    }
+
+    //ShapeFromFile keeps the entities of each file in its own list, Merge would not
+    let mut lines = vec![String::from("SetFactory(\"OpenCASCADE\");")];
+    lines.extend(units.option_lines());
+    lines.push(String::from("import_volume_count = 0;"));
+    for (part_idx, part) in parts.iter().enumerate() {
+        let list = part_list_name(part_idx);
+        let unit_factor = match part.format() {
+            GeometryFormat::Brep => units.unitless_factor(),
+            _ => 1.0, //converted by OpenCASCADE
+        };
+        lines.push(format!("{}() = ShapeFromFile(\"{}\");", list, part.file));
+        //a part adding no volumes is made of surfaces
+        lines.push(String::from("import_volumes[] = Volume{:};"));
+        lines.push(format!(
+            "{}_solid = #import_volumes[] > import_volume_count;",
+            list
+        ));
+        lines.push(String::from("import_volume_count = #import_volumes[];"));
+        lines.extend(part.placement_lines(&list, unit_factor));
+    }
+    lines
+}
+
+///Every part must be a supported file that exists, a .geo script can only be used alone
+pub fn validate_parts(parts: &[GeometryPart]) -> Result<()> {
+    if parts.is_empty() {
+        return Err(TermshError::Input(format!(
+            "please input a geometry file ({})",
+            SUPPORTED_EXTENSIONS
+        )));
+    }
+
+    for part in parts {
+        let Some(format) = GeometryFormat::detect(&part.file) else {
+            return Err(TermshError::Input(format!(
+                "{} is not a supported geometry file, expected {}",
+                part.file, SUPPORTED_EXTENSIONS
+            )));
+        };
+
+        if !Path::new(&part.file).exists() {
+            return Err(TermshError::Input(format!(
+                "file {} does not exist",
+                part.file
+            )));
+        }
+
+        if !format.is_cad() && (parts.len() > 1 || part.is_moved()) {
+            return Err(TermshError::Input(format!(
+                "{} is a .geo script, it can not be combined with other files, scaled or translated",
+                part.file
+            )));
+        }
+
+        if part.scale <= 0.0 {
+            return Err(TermshError::Input(format!(
+                "scale of {} must be greater than 0",
+                part.file
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_by_extension() {
+        assert_eq!(
+            GeometryFormat::detect("part.STEP"),
+            Some(GeometryFormat::Step)
+        );
+        assert_eq!(
+            GeometryFormat::detect("dir/part.Stp"),
+            Some(GeometryFormat::Step)
+        );
+        assert_eq!(
+            GeometryFormat::detect("part.igs"),
+            Some(GeometryFormat::Iges)
+        );
+        assert_eq!(
+            GeometryFormat::detect("part.brep"),
+            Some(GeometryFormat::Brep)
+        );
+        assert_eq!(
+            GeometryFormat::detect("model.geo"),
+            Some(GeometryFormat::Geo)
+        );
+        assert_eq!(GeometryFormat::detect("part.step.bak"), None);
+        assert_eq!(GeometryFormat::detect("part"), None);
+        assert_eq!(GeometryFormat::detect("step"), None);
+    }
+
+    #[test]
+    fn unsupported_files_are_refused() {
+        for file in ["part.step.bak", "part"] {
+            let parts = [GeometryPart::new(String::from(file))];
+            let error = validate_parts(&parts).unwrap_err().to_string();
+            assert!(error.contains("not a supported geometry file"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn cad_parts_are_imported_and_scaled_from_the_source_unit() {
+        let units = Units {
+            source: LengthUnit::M,
+            target: LengthUnit::Mm,
+        };
+        let mut brep = GeometryPart::new(String::from("plate.brep"));
+        brep.translation = [5.0, 0.0, 0.0];
+        let parts = [GeometryPart::new(String::from("housing.STP")), brep];
+        let lines = import_lines(&parts, units);
+
+        for expected in [
+            "part_1() = ShapeFromFile(\"housing.STP\");",
+            "part_2() = ShapeFromFile(\"plate.brep\");",
+            "  Dilate {{0, 0, 0}, 1000} { Volume{part_2()}; }",
+            "  Translate {5, 0, 0} { Surface{part_2()}; }",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "{}\n{:#?}",
+                expected,
+                lines
+            );
+        }
+        //OpenCASCADE converts the STEP file, it is not scaled again
+        assert!(!lines.iter().any(|line| line.contains("Volume{part_1()}")));
+        //mm is the OpenCASCADE default
+        assert!(!lines.iter().any(|line| line.contains("OCCTargetUnit")));
+    }
+
+    #[test]
+    fn geo_script_is_merged_and_scaled() {
+        let parts = [GeometryPart::new(String::from("model.geo"))];
+        assert_eq!(
+            import_lines(&parts, Units::default()),
+            ["Merge \"model.geo\";"]
+        );
+
+        let units = Units {
+            source: LengthUnit::Mm,
+            target: LengthUnit::M,
+        };
+        let lines = import_lines(&parts, units);
+        assert_eq!(lines[0], "Merge \"model.geo\";");
+        assert!(lines.contains(&String::from("  Dilate {{0, 0, 0}, 0.001} { Volume{:}; }")));
+        assert!(lines.contains(&String::from("  Dilate {{0, 0, 0}, 0.001} { Surface{:}; }")));
+        assert!(!lines.iter().any(|line| line.contains("OpenCASCADE")));
+    }
+
+    #[test]
+    fn target_unit_for_step_and_iges() {
+        let units = Units {
+            source: LengthUnit::Mm,
+            target: LengthUnit::In,
+        };
+        let parts = [GeometryPart::new(String::from("cover.iges"))];
+        let lines = import_lines(&parts, units);
+        assert_eq!(lines[1], "Geometry.OCCTargetUnit=\"INCH\";");
+        assert!(!lines.iter().any(|line| line.contains("Dilate")));
+    }
+}
@@ -1,289 +1,1741 @@
 #![allow(dead_code)]
 
 use std::{
-    fs::{self, read_to_string},
-    io,
+    collections::BTreeMap,
+    fmt, fs,
     path::Path,
     process::{Child, Stdio},
 };
 
 use serde::{Deserialize, Serialize};
 
-///All use String because this will be put in .geo script as String
-#[derive(Debug, Clone)]
+use crate::boundary_condition::{BcKind, BoundaryCondition};
+use crate::boundary_layer::BoundaryLayer;
+use crate::entity::{EntityList, part_query_lines, query_script_lines};
+use crate::error::{Result, TermshError};
+use crate::field::{MeshField, field_problems};
+use crate::geometry::{self, GeometryPart, Units};
+use crate::gmsh_log::GmshLog;
+use crate::gmsh_option::{MESH_OPTIONS, validate_advanced};
+use crate::id_set::IdSet;
+use crate::material::{MATERIAL_FILE, MaterialLibrary};
+
+///Name and Physical ID use String because they will be put in .geo script as String
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VolPhys {
     pub name: String,
     pub phys_id: String,
-    pub vol_ids: String,
+    pub vol_ids: IdSet,
+    #[serde(default)]
+    pub mesh_size: String, //element size in the volumes, empty to leave it to the Mesh tab
+    #[serde(default)]
+    pub material: String, //name in the MaterialLibrary, empty when not assigned
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SurfPhys {
     pub name: String,
     pub phys_id: String,
-    pub surf_ids: String,
+    pub surf_ids: IdSet,
+    #[serde(default)]
+    pub mesh_size: String, //element size on the surfaces, empty to leave it to the Mesh tab
+    #[serde(default)]
+    pub boundary_conditions: Vec<BoundaryCondition>,
+    #[serde(default)]
+    pub material: String, //shell material in a 2D model, from the MaterialLibrary
+    #[serde(default)]
+    pub thickness: String, //shell thickness in a 2D model, in the target unit
 }
 
-#[derive(Debug, Clone)]
+///Curves of a group, eg. line loads or weld seams, the ids are not checked against the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurvPhys {
+    pub name: String,
+    pub phys_id: String,
+    pub curve_ids: IdSet,
+    #[serde(default)]
+    pub boundary_layer: bool, //boundary layers grow from these curves in a 2D model
+    #[serde(default)]
+    pub boundary_conditions: Vec<BoundaryCondition>,
+}
+
+///Points of a group, eg. point loads or sensor locations, the ids are not checked against the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PointPhys {
+    pub name: String,
+    pub phys_id: String,
+    pub point_ids: IdSet,
+    #[serde(default)]
+    pub boundary_conditions: Vec<BoundaryCondition>,
+}
+
+///Element size of a single volume or surface, set from the Entities table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMeshSize {
+    pub dim: u8, //3 for a volume, 2 for a surface
+    pub tag: u32,
+    pub size: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MeshPara {
-    pub max_size: String, // if None, Mesh Max Size will not be set
-                          //add more parameters in future
+    //Gmsh option name to value, eg. "Mesh.MeshSizeMax" -> "5", see MESH_OPTIONS
+    //options which are not here keep the Gmsh default
+    pub options: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone)]
+impl MeshPara {
+    pub fn get(&self, name: &str) -> &str {
+        self.options.get(name).map_or("", String::as_str)
+    }
+
+    ///an empty value goes back to the Gmsh default
+    pub fn set(&mut self, name: &str, value: String) {
+        if value.is_empty() {
+            self.options.remove(name);
+        } else {
+            self.options.insert(name.to_string(), value);
+        }
+    }
+}
+
+///Transfinite (structured) meshing of a single volume or surface, set from the Entities table
+///a transfinite volume needs 5 or 6 faces, a transfinite surface 3 or 4 corners
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuredEntity {
+    pub dim: u8, //3 for a volume, 2 for a surface
+    pub tag: u32,
+    #[serde(default)]
+    pub nodes: String, //nodes on every curve of the entity, empty when not transfinite
+    #[serde(default)]
+    pub recombine: bool, //quadrangles on the surfaces, hexahedra in a transfinite volume
+}
+
+///Any Gmsh option, for what the Mesh and Geometry tabs do not cover
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvancedOption {
+    pub name: String,  //eg. "Mesh.RecombineAll", see ADVANCED_OPTIONS
+    pub value: String, //checked by validate_advanced
+    #[serde(default)]
+    pub unchecked: bool, //passed to Gmsh although it is not in the catalog
+}
+
+impl AdvancedOption {
+    ///Mesh.* options go with the Mesh tab, the others before the import
+    pub fn is_mesh(&self) -> bool {
+        self.name.starts_with("Mesh.")
+    }
+}
+
+///How CAD geometry is repaired on import and fused afterwards
+///the OCC options act while the file is read, so they are set before the import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealingPara {
+    pub boolean_fragments: bool, //fuse touching volumes and parts, so they share surfaces
+    pub coherence: bool,
+    pub tolerance: String, //Geometry.Tolerance, empty to keep the Gmsh default
+    pub fix_degenerated: bool,
+    pub fix_small_edges: bool,
+    pub fix_small_faces: bool,
+    pub sew_faces: bool,
+    pub make_solids: bool,
+}
+
+impl Default for HealingPara {
+    fn default() -> Self {
+        HealingPara {
+            boolean_fragments: true,
+            coherence: true,
+            tolerance: String::new(),
+            fix_degenerated: false,
+            fix_small_edges: false,
+            fix_small_faces: false,
+            sew_faces: false,
+            make_solids: false,
+        }
+    }
+}
+
+///Dimension of the mesh, shells for surface models (eg. thin-walled STEP surfaces) and solids otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelDimension {
+    #[default]
+    Auto, //2 when the queried model has surfaces but no volume, else 3
+    Solid,
+    Shell,
+}
+
+impl ModelDimension {
+    pub const ALL: [ModelDimension; 3] = [
+        ModelDimension::Auto,
+        ModelDimension::Solid,
+        ModelDimension::Shell,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ModelDimension::Auto => "auto",
+            ModelDimension::Solid => "3",
+            ModelDimension::Shell => "2",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ModelDimension::Auto => "From the queried entities",
+            ModelDimension::Solid => "Solid elements in the volumes",
+            ModelDimension::Shell => "Shell elements on the surfaces",
+        }
+    }
+}
+
+impl fmt::Display for ModelDimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl std::str::FromStr for ModelDimension {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ModelDimension::ALL
+            .into_iter()
+            .find(|dimension| dimension.label() == s.trim())
+            .ok_or_else(|| format!("\"{}\" is not a dimension, expected auto/3/2", s.trim()))
+    }
+}
+
+///Mesh file formats that can be exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ExportFormat {
+    Nas,      //Nastran bulk data, small field
+    NasLarge, //Nastran bulk data, large field
+    NasFree,  //Nastran bulk data, free field
+    Msh22,    //Gmsh MSH v2.2
+    Msh41,    //Gmsh MSH v4.1
+    Inp,      //Abaqus / CalculiX
+    Unv,      //I-deas universal
+    Vtk,      //legacy VTK
+    Med,      //Salome MED
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 9] = [
+        ExportFormat::Nas,
+        ExportFormat::NasLarge,
+        ExportFormat::NasFree,
+        ExportFormat::Msh22,
+        ExportFormat::Msh41,
+        ExportFormat::Inp,
+        ExportFormat::Unv,
+        ExportFormat::Vtk,
+        ExportFormat::Med,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Nas => "Nastran small",
+            ExportFormat::NasLarge => "Nastran large",
+            ExportFormat::NasFree => "Nastran free",
+            ExportFormat::Msh22 => "MSH 2.2",
+            ExportFormat::Msh41 => "MSH 4.1",
+            ExportFormat::Inp => "Abaqus INP",
+            ExportFormat::Unv => "UNV",
+            ExportFormat::Vtk => "VTK",
+            ExportFormat::Med => "MED",
+        }
+    }
+
+    ///file name ending, formats sharing an extension get a suffix to not overwrite each other
+    fn file_suffix(&self) -> &'static str {
+        match self {
+            ExportFormat::Nas => ".nas",
+            ExportFormat::NasLarge => "_large.nas",
+            ExportFormat::NasFree => "_free.nas",
+            ExportFormat::Msh22 => "_v22.msh",
+            ExportFormat::Msh41 => ".msh",
+            ExportFormat::Inp => ".inp",
+            ExportFormat::Unv => ".unv",
+            ExportFormat::Vtk => ".vtk",
+            ExportFormat::Med => ".med",
+        }
+    }
+
+    pub fn is_nastran(&self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Nas | ExportFormat::NasLarge | ExportFormat::NasFree
+        )
+    }
+
+    ///Mesh.Format and the format specific options
+    fn format_lines(&self) -> Vec<String> {
+        let lines: &[&str] = match self {
+            //Mesh.BdfFieldFormat: 0 free, 1 small, 2 large
+            ExportFormat::Nas => &["Mesh.Format=31;", "Mesh.BdfFieldFormat=1;"],
+            ExportFormat::NasLarge => &["Mesh.Format=31;", "Mesh.BdfFieldFormat=2;"],
+            ExportFormat::NasFree => &["Mesh.Format=31;", "Mesh.BdfFieldFormat=0;"],
+            ExportFormat::Msh22 => &["Mesh.Format=1;", "Mesh.MshFileVersion=2.2;"],
+            ExportFormat::Msh41 => &["Mesh.Format=1;", "Mesh.MshFileVersion=4.1;"],
+            ExportFormat::Inp => &["Mesh.Format=39;"],
+            ExportFormat::Unv => &["Mesh.Format=2;"],
+            ExportFormat::Vtk => &["Mesh.Format=16;"],
+            ExportFormat::Med => &["Mesh.Format=33;"],
+        };
+
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+}
+
+///Per format export options, every format has one entry and is written when enabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSetting {
+    pub format: ExportFormat,
+    pub enabled: bool,
+    pub save_all: bool,       //false: only save Physical Objects
+    pub element_tag_type: u8, //1: elementary, 2: physical, 3: partition
+    #[serde(default)]
+    pub nastran_deck: bool, //complete the Nastran file into a deck, see nastran::write_deck
+}
+
+impl ExportSetting {
+    pub fn new(format: ExportFormat) -> Self {
+        ExportSetting {
+            format,
+            enabled: format == ExportFormat::Nas,
+            save_all: false,
+            element_tag_type: 2,
+            nastran_deck: false,
+        }
+    }
+}
+
+//missing fields take their default value, so older project files still load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GmshPara {
-    pub geometry_file: String, //filename of the geometry, eg. xxx.step
+    pub geometry_parts: Vec<GeometryPart>, //geometry files fused into one model, see GeometryFormat
+    pub units: Units,
+    pub model_dimension: ModelDimension,
+    #[serde(skip)]
+    pub detected_dimension: Option<u8>, //from the last entity query, used by ModelDimension::Auto
     pub vol_phy_list: Vec<VolPhys>,
     pub surf_phy_list: Vec<SurfPhys>,
+    pub curve_phy_list: Vec<CurvPhys>,
+    pub point_phy_list: Vec<PointPhys>,
+    pub healing_paras: HealingPara,
     pub mesh_paras: MeshPara,
+    pub advanced_options: Vec<AdvancedOption>, //Mesh.* after the Mesh tab options, others before the import
+    pub entity_mesh_sizes: Vec<EntityMeshSize>, //override the sizes of the groups
+    pub fields: Vec<MeshField>,
+    pub background_field: Option<u32>, //id of the field giving the element size
+    pub boundary_layer: BoundaryLayer,
+    pub structured_entities: Vec<StructuredEntity>,
+    pub export_settings: Vec<ExportSetting>,
+}
+
+impl Default for GmshPara {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GmshPara {
     pub fn new() -> Self {
-        let mut new_gmsh_para = GmshPara {
-            geometry_file: String::new(),
+        GmshPara {
+            geometry_parts: Vec::new(),
+            units: Units::default(),
+            model_dimension: ModelDimension::Auto,
+            detected_dimension: None,
             vol_phy_list: Vec::new(),
             surf_phy_list: Vec::new(),
-            mesh_paras: MeshPara {
-                max_size: String::new(),
-            },
-        };
+            curve_phy_list: Vec::new(),
+            point_phy_list: Vec::new(),
+            healing_paras: HealingPara::default(),
+            mesh_paras: MeshPara::default(),
+            advanced_options: Vec::new(),
+            entity_mesh_sizes: Vec::new(),
+            fields: Vec::new(),
+            background_field: None,
+            boundary_layer: BoundaryLayer::default(),
+            structured_entities: Vec::new(),
+            export_settings: ExportFormat::ALL.map(ExportSetting::new).to_vec(),
+        }
+    }
+
+    ///Make sure every format has exactly one setting, in the order of ExportFormat::ALL
+    ///needed after loading a project written before a format existed
+    pub fn complete_export_settings(&mut self) {
+        self.export_settings = ExportFormat::ALL
+            .iter()
+            .map(|format| {
+                self.export_settings
+                    .iter()
+                    .find(|setting| setting.format == *format)
+                    .cloned()
+                    .unwrap_or_else(|| ExportSetting::new(*format))
+            })
+            .collect();
+    }
+
+    ///only enable the given formats, keeping their other options
+    pub fn select_export_formats(&mut self, formats: &[ExportFormat]) {
+        for setting in &mut self.export_settings {
+            setting.enabled = formats.contains(&setting.format);
+        }
+    }
+
+    pub fn enabled_exports(&self) -> Vec<&ExportSetting> {
+        self.export_settings
+            .iter()
+            .filter(|setting| setting.enabled)
+            .collect()
+    }
+
+    pub fn apply_mesh(&self) -> Result<Child> {
+        self.open_gui(ScriptAction::Preview)
+    }
 
-        //check if there is a termsh_cache.json
-        let cache_filename = "termsh_cache.json";
+    pub fn apply_mesh_and_export(&self) -> Result<Child> {
+        self.open_gui(ScriptAction::Export)
+    }
 
-        if Path::new(cache_filename).exists() {
-            let cache_content = read_to_string(cache_filename).expect("Error Reading Cache File");
-            let phys_pair_list: PhysPairList =
-                serde_json::from_str(&cache_content).expect("Error Deserialize");
+    fn open_gui(&self, action: ScriptAction) -> Result<Child> {
+        let temp_script_file_name = self.write_script(action)?;
 
-            for vol_pair in phys_pair_list.vol_pairs {
-                new_gmsh_para.vol_phy_list.push(VolPhys {
-                    name: vol_pair.name,
-                    phys_id: vol_pair.phys_id,
-                    vol_ids: String::new(),
-                });
+        //spawn Gmsh Child Process, its output is read by GmshLog::capture
+        std::process::Command::new("gmsh")
+            .arg(&temp_script_file_name)
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(TermshError::GmshStart)
+    }
+
+    ///Mesh and save all enabled formats without opening the Gmsh GUI, wait until Gmsh exits
+    ///Succeed only when Gmsh reports no error, the mesh is not empty and every output file is written
+    pub fn export_batch(&self) -> Result<GmshLog> {
+        let output_file_names: Vec<String> = self
+            .enabled_exports()
+            .iter()
+            .map(|setting| self.output_file_name(setting.format))
+            .collect();
+        if output_file_names.is_empty() {
+            return Err(TermshError::Input(String::from(
+                "no export format is enabled",
+            )));
+        }
+
+        //remove the old outputs first, so a failed run is not mistaken as success
+        for output_file_name in &output_file_names {
+            if Path::new(output_file_name).exists() {
+                fs::remove_file(output_file_name).map_err(TermshError::io(format!(
+                    "failed to remove old {}",
+                    output_file_name
+                )))?;
             }
+        }
 
-            for sur_pair in phys_pair_list.sur_pairs {
-                new_gmsh_para.surf_phy_list.push(SurfPhys {
-                    name: sur_pair.name,
-                    phys_id: sur_pair.phys_id,
-                    surf_ids: String::new(),
-                });
+        let gmsh_log = self.run_batch(ScriptAction::Export)?;
+
+        if let Some(failure) = gmsh_log.failure() {
+            return Err(TermshError::Gmsh(format!("meshing failed: {}", failure)));
+        }
+
+        for output_file_name in &output_file_names {
+            if !fs::metadata(output_file_name).is_ok_and(|m| m.len() > 0) {
+                return Err(TermshError::Gmsh(format!(
+                    "Gmsh finished but {} was not written",
+                    output_file_name
+                )));
             }
         }
 
-        new_gmsh_para
+        Ok(gmsh_log)
     }
 
-    pub fn apply_mesh(&self) -> (io::Result<Child>, String) {
-        let mut scirpt_content = String::new();
+    ///Run Gmsh in batch mode on the healed geometry and list the volumes and surfaces it has
+    ///This blocks until Gmsh exits, call it from a background thread in the TUI
+    pub fn query_entities(&self) -> Result<EntityList> {
+        let query_output_file_name = self.query_output_file_name();
+        if Path::new(&query_output_file_name).exists() {
+            fs::remove_file(&query_output_file_name).map_err(TermshError::io(format!(
+                "failed to remove {}",
+                query_output_file_name
+            )))?;
+        }
 
-        //generate geometry file readin scripts
-        //using this is better than Merge
-        scirpt_content += &format!(
-            "SetFactory(\"OpenCASCADE\");\n\
-            v()=ShapeFromFile(\"{}\");\n",
-            self.geometry_file
-        );
+        let gmsh_log = self.run_batch(ScriptAction::QueryEntities)?;
 
-        //generate geometry healing scripts
-        scirpt_content += &format!(
-            "/* Heal the step file */\n\
-            BooleanFragments{{ Volume{{:}}; Surface {{:}}; Delete; }}{{}}\n\
-            Coherence;\n"
-        );
+        if let Some(failure) = gmsh_log.failure() {
+            return Err(TermshError::Gmsh(failure));
+        }
 
-        //generate Physical Volume scripts
-        scirpt_content += &format!("/* Physical Volume Grouping */\n");
+        //no output file means the model has no volume and no surface
+        if !Path::new(&query_output_file_name).exists() {
+            return Ok(EntityList::default());
+        }
 
-        for vol_phys in &self.vol_phy_list {
-            if vol_phys.vol_ids.is_empty() {
-                continue; //skip empty content
+        let entity_list = EntityList::read_query_output(&query_output_file_name);
+        fs::remove_file(&query_output_file_name).map_err(TermshError::io(format!(
+            "failed to remove {}",
+            query_output_file_name
+        )))?;
+        entity_list
+    }
+
+    ///Run the script of an action without GUI and wait, the script file is removed afterwards
+    fn run_batch(&self, action: ScriptAction) -> Result<GmshLog> {
+        let temp_script_file_name = self.write_script(action)?;
+
+        //the trailing "-" makes Gmsh parse the script and exit, without GUI
+        let output = std::process::Command::new("gmsh")
+            .arg(&temp_script_file_name)
+            .arg("-")
+            .arg("-nopopup")
+            .output();
+
+        fs::remove_file(&temp_script_file_name).map_err(TermshError::io(format!(
+            "failed to remove {}",
+            temp_script_file_name
+        )))?;
+
+        let output = output.map_err(TermshError::GmshStart)?;
+        let gmsh_log = GmshLog::from_output(&output);
+
+        if !output.status.success() {
+            let reason = gmsh_log.failure().unwrap_or_default();
+            return Err(TermshError::Gmsh(format!(
+                "Gmsh exited with {} {}",
+                output.status, reason
+            )));
+        }
+
+        Ok(gmsh_log)
+    }
+
+    fn query_output_file_name(&self) -> String {
+        self.filename_prefix() + "_entities_temp.txt"
+    }
+
+    ///"POINT tag x y z" lines of the points carrying boundary conditions, read by the Nastran decks
+    pub fn point_output_file_name(&self) -> String {
+        self.filename_prefix() + "_points_temp.txt"
+    }
+
+    pub fn output_file_name(&self, format: ExportFormat) -> String {
+        self.filename_prefix() + format.file_suffix()
+    }
+
+    //output files are named after the first part
+    fn filename_prefix(&self) -> String {
+        let first_file = self
+            .geometry_parts
+            .first()
+            .map_or("", |part| part.file.as_str());
+        Path::new(first_file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    ///Compose the whole .geo script for an action, section by section
+    pub fn build_script(&self, action: ScriptAction) -> GeoScript {
+        let mut script = GeoScript::new();
+
+        let is_cad_model = geometry::is_cad_model(&self.geometry_parts);
+
+        //General.* and Geometry.* options only act on what is read after them
+        script.push_section("General Options", self.general_option_lines());
+        script.push_section("Healing Options", self.healing_option_lines());
+        script.push_section("Import Geometry", self.import_lines());
+        if is_cad_model {
+            //where the parts are before healing, to tell which part each volume comes from
+            if let ScriptAction::QueryEntities = action {
+                script.push_section(
+                    "Query Parts",
+                    part_query_lines(self.geometry_parts.len(), &self.query_output_file_name()),
+                );
             }
-            scirpt_content += &format!(
-                "Physical Volume(\"{}\",{})={{{}}};\n",
-                vol_phys.name, vol_phys.phys_id, vol_phys.vol_ids
+            script.push_section("Heal the CAD geometry", self.healing_lines());
+        }
+
+        if let ScriptAction::QueryEntities = action {
+            script.push_section(
+                "Query Entities",
+                query_script_lines(&self.query_output_file_name()),
             );
+            return script;
         }
 
-        //generate Physical Surface scripts
-        scirpt_content += &format!("/* Physical Surface Grouping */\n");
+        script.push_section("Physical Volume Grouping", self.phys_vol_lines());
+        script.push_section("Physical Surface Grouping", self.phys_surf_lines());
+        script.push_section("Physical Curve Grouping", self.phys_curve_lines());
+        script.push_section("Physical Point Grouping", self.phys_point_lines());
+        script.push_section("Mesh Setting", self.mesh_option_lines());
+        script.push_section("Local Mesh Size", self.local_size_lines());
+        script.push_section("Mesh Size Fields", self.field_lines());
+        script.push_section("Boundary Layers", self.boundary_layer_lines());
+        script.push_section("Structured Mesh", self.structured_lines());
+        script.push_section("Meshing", self.meshing_lines());
 
-        for sur_phys in &self.surf_phy_list {
-            if sur_phys.surf_ids.is_empty() {
-                continue; //skip empty content
+        if let ScriptAction::Export = action {
+            script.push_section("Point Coordinates", self.point_coordinate_lines());
+            for setting in self.enabled_exports() {
+                script.push_section(
+                    &format!("Export {}", setting.format.label()),
+                    self.export_lines(setting),
+                );
             }
-            scirpt_content += &format!(
-                "Physical Surface(\"{}\",{})={{{}}};\n",
-                sur_phys.name, sur_phys.phys_id, sur_phys.surf_ids
-            );
         }
 
-        //generate Mesh Parameter scripts
-        scirpt_content += &format!("/* Mesh Setting */\n");
+        script
+    }
 
-        if !self.mesh_paras.max_size.is_empty() {
-            scirpt_content += &format!("Mesh.MeshSizeMax={};\n", self.mesh_paras.max_size);
+    ///write the script of an action, return the temporary script file name
+    fn write_script(&self, action: ScriptAction) -> Result<String> {
+        //mesh_dimension would guess 3D, a shell model would be exported as volumes
+        if let ScriptAction::Export = action
+            && self.dimension_pending()
+        {
+            return Err(TermshError::Input(String::from(
+                "the model dimension is not detected yet, wait for the entity query or set it to 2 or 3",
+            )));
         }
 
-        //perform meshing scripts
-        scirpt_content += &format!("Mesh 3;\n");
+        //the script appends to it, points of an earlier export must not be read again
+        let point_output_file_name = self.point_output_file_name();
+        if let ScriptAction::Export = action
+            && Path::new(&point_output_file_name).exists()
+        {
+            fs::remove_file(&point_output_file_name).map_err(TermshError::io(format!(
+                "failed to remove {}",
+                point_output_file_name
+            )))?;
+        }
 
-        //get filename_prefix
-        let filename_prefix = Path::new(&self.geometry_file)
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap();
+        let temp_script_file_name = self.temp_script_file_name(action);
+        fs::write(&temp_script_file_name, self.build_script(action).render()).map_err(
+            TermshError::io(format!("failed to write {}", temp_script_file_name)),
+        )?;
 
-        //export content to temporary script file
-        let temp_script_file_name = filename_prefix.to_owned() + "_temp.geo";
-        if let Err(err) = fs::write(&temp_script_file_name, scirpt_content) {
-            panic!("Error when writing to temporary script file: {}", err)
+        Ok(temp_script_file_name)
+    }
+
+    pub fn temp_script_file_name(&self, action: ScriptAction) -> String {
+        //the query runs at the same time as the Gmsh GUI, so it needs its own file
+        match action {
+            ScriptAction::QueryEntities => self.filename_prefix() + "_query_temp.geo",
+            _ => self.filename_prefix() + "_temp.geo",
         }
+    }
 
-        //spawn Gmsh Child Process
-        let gmsh_child_handle = std::process::Command::new("gmsh")
-            .arg(&temp_script_file_name)
-            .stderr(Stdio::null())
-            .stdout(Stdio::null())
-            .spawn()
-            .expect("failed to start gmsh");
+    fn import_lines(&self) -> Vec<String> {
+        geometry::import_lines(&self.geometry_parts, self.units)
+    }
 
-        (Ok(gmsh_child_handle), temp_script_file_name)
+    fn healing_option_lines(&self) -> Vec<String> {
+        let healing = &self.healing_paras;
+        let mut lines = Vec::new();
+
+        if !healing.tolerance.is_empty() {
+            lines.push(format!("Geometry.Tolerance={};", healing.tolerance));
+        }
+
+        //Gmsh leaves all of them off by default
+        for (option, enabled) in [
+            ("OCCFixDegenerated", healing.fix_degenerated),
+            ("OCCFixSmallEdges", healing.fix_small_edges),
+            ("OCCFixSmallFaces", healing.fix_small_faces),
+            ("OCCSewFaces", healing.sew_faces),
+            ("OCCMakeSolids", healing.make_solids),
+        ] {
+            if enabled {
+                lines.push(format!("Geometry.{}=1;", option));
+            }
+        }
+
+        lines
     }
 
-    pub fn apply_mesh_and_save_to_nas(&self) -> (io::Result<Child>, String) {
-        let mut scirpt_content = String::new();
+    fn healing_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
 
-        //generate geometry file readin scripts
-        //using this is better than Merge
-        scirpt_content += &format!(
-            "SetFactory(\"OpenCASCADE\");\n\
-            v()=ShapeFromFile(\"{}\");\n",
-            self.geometry_file
-        );
+        if self.healing_paras.boolean_fragments {
+            lines.push(String::from(
+                "BooleanFragments{ Volume{:}; Surface {:}; Delete; }{}",
+            ));
+        }
+        if self.healing_paras.coherence {
+            lines.push(String::from("Coherence;"));
+        }
 
-        //generate geometry healing scripts
-        scirpt_content += &format!(
-            "/* Heal the step file */\n\
-            BooleanFragments{{ Volume{{:}}; Surface {{:}}; Delete; }}{{}}\n\
-            Coherence;\n"
-        );
+        lines
+    }
+
+    fn phys_vol_lines(&self) -> Vec<String> {
+        self.vol_phy_list
+            .iter()
+            .filter(|vol_phys| !vol_phys.vol_ids.is_empty()) //skip empty content
+            .map(|vol_phys| {
+                format!(
+                    "Physical Volume(\"{}\",{})={{{}}};",
+                    vol_phys.name, vol_phys.phys_id, vol_phys.vol_ids
+                )
+            })
+            .collect()
+    }
+
+    fn phys_surf_lines(&self) -> Vec<String> {
+        self.surf_phy_list
+            .iter()
+            .filter(|sur_phys| !sur_phys.surf_ids.is_empty()) //skip empty content
+            .map(|sur_phys| {
+                format!(
+                    "Physical Surface(\"{}\",{})={{{}}};",
+                    sur_phys.name, sur_phys.phys_id, sur_phys.surf_ids
+                )
+            })
+            .collect()
+    }
+
+    fn phys_curve_lines(&self) -> Vec<String> {
+        self.curve_phy_list
+            .iter()
+            .filter(|curve_phys| !curve_phys.curve_ids.is_empty()) //skip empty content
+            .map(|curve_phys| {
+                format!(
+                    "Physical Curve(\"{}\",{})={{{}}};",
+                    curve_phys.name, curve_phys.phys_id, curve_phys.curve_ids
+                )
+            })
+            .collect()
+    }
+
+    fn phys_point_lines(&self) -> Vec<String> {
+        self.point_phy_list
+            .iter()
+            .filter(|point_phys| !point_phys.point_ids.is_empty()) //skip empty content
+            .map(|point_phys| {
+                format!(
+                    "Physical Point(\"{}\",{})={{{}}};",
+                    point_phys.name, point_phys.phys_id, point_phys.point_ids
+                )
+            })
+            .collect()
+    }
+
+    fn mesh_option_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for option in &MESH_OPTIONS {
+            let value = self.mesh_paras.get(option.name);
+            if !value.is_empty() {
+                lines.push(format!("{}={};", option.name, value));
+            }
+        }
+
+        for option in self
+            .advanced_options
+            .iter()
+            .filter(|option| option.is_mesh())
+        {
+            lines.push(format!("{}={};", option.name, option.value));
+        }
+
+        lines
+    }
+
+    fn general_option_lines(&self) -> Vec<String> {
+        self.advanced_options
+            .iter()
+            .filter(|option| !option.is_mesh())
+            .map(|option| format!("{}={};", option.name, option.value))
+            .collect()
+    }
+
+    ///size set on a single entity, empty when it takes the size of its group
+    pub fn entity_mesh_size(&self, dim: u8, tag: u32) -> &str {
+        self.entity_mesh_sizes
+            .iter()
+            .find(|entity_size| entity_size.dim == dim && entity_size.tag == tag)
+            .map_or("", |entity_size| entity_size.size.as_str())
+    }
 
-        //generate Physical Volume scripts
-        scirpt_content += &format!("/* Physical Volume Grouping */\n");
+    ///an empty size removes the override
+    pub fn set_entity_mesh_size(&mut self, dim: u8, tag: u32, size: String) {
+        self.entity_mesh_sizes
+            .retain(|entity_size| !(entity_size.dim == dim && entity_size.tag == tag));
+        if !size.is_empty() {
+            self.entity_mesh_sizes
+                .push(EntityMeshSize { dim, tag, size });
+        }
+    }
+
+    //sizes are put on the points of the entities, a point shared by several entities
+    //takes the last size, so the order is group volumes, entity volumes, group surfaces
+    //and entity surfaces: surfaces refine their volumes and single entities their groups
+    fn local_size_lines(&self) -> Vec<String> {
+        let vol_groups = self
+            .vol_phy_list
+            .iter()
+            .map(|vol_phys| (3, &vol_phys.vol_ids, &vol_phys.mesh_size));
+        let surf_groups = self
+            .surf_phy_list
+            .iter()
+            .map(|surf_phys| (2, &surf_phys.surf_ids, &surf_phys.mesh_size));
+        let groups: Vec<(u8, &IdSet, &String)> = vol_groups.chain(surf_groups).collect();
+
+        let mut lines = Vec::new();
+        for dim in [3, 2] {
+            let kind = if dim == 3 { "Volume" } else { "Surface" };
+
+            for (_, ids, size) in groups.iter().filter(|(group_dim, ..)| *group_dim == dim) {
+                if !size.is_empty() && !ids.is_empty() {
+                    lines.push(format!(
+                        "MeshSize{{ PointsOf{{ {}{{{}}}; }} }} = {};",
+                        kind, ids, size
+                    ));
+                }
+            }
+
+            for entity_size in self
+                .entity_mesh_sizes
+                .iter()
+                .filter(|entity_size| entity_size.dim == dim)
+            {
+                lines.push(format!(
+                    "MeshSize{{ PointsOf{{ {}{{{}}}; }} }} = {};",
+                    kind, entity_size.tag, entity_size.size
+                ));
+            }
+        }
+
+        lines
+    }
+
+    pub fn structured_entity(&self, dim: u8, tag: u32) -> Option<&StructuredEntity> {
+        self.structured_entities
+            .iter()
+            .find(|structured| structured.dim == dim && structured.tag == tag)
+    }
+
+    ///replaces the setting of the same entity, one without nodes and recombine is removed
+    pub fn set_structured_entity(&mut self, structured: StructuredEntity) {
+        self.structured_entities
+            .retain(|other| !(other.dim == structured.dim && other.tag == structured.tag));
+        if !structured.nodes.is_empty() || structured.recombine {
+            self.structured_entities.push(structured);
+        }
+    }
+
+    //the surfaces and curves of each entity are listed first, then every curve gets its
+    //node count before the surfaces and volumes using them are made transfinite
+    fn structured_lines(&self) -> Vec<String> {
+        let mut list_lines = Vec::new();
+        let mut curve_lines = Vec::new();
+        let mut surface_lines = Vec::new();
+        let mut recombine_lines = Vec::new();
+        let mut volume_lines = Vec::new();
+
+        for structured in &self.structured_entities {
+            let (surfaces, curves) = if structured.dim == 3 {
+                let surfaces = format!("tf_s_v{}[]", structured.tag);
+                list_lines.push(format!(
+                    "{} = Abs(Boundary{{ Volume{{{}}}; }});",
+                    surfaces, structured.tag
+                ));
+                (surfaces, format!("tf_c_v{}[]", structured.tag))
+            } else {
+                (
+                    structured.tag.to_string(),
+                    format!("tf_c_s{}[]", structured.tag),
+                )
+            };
+
+            if !structured.nodes.is_empty() {
+                list_lines.push(format!(
+                    "{} = Unique(Abs(Boundary{{ Surface{{{}}}; }}));",
+                    curves, surfaces
+                ));
+                curve_lines.push(format!(
+                    "Transfinite Curve{{{}}} = {};",
+                    curves, structured.nodes
+                ));
+                surface_lines.push(format!("Transfinite Surface{{{}}};", surfaces));
+                if structured.dim == 3 {
+                    volume_lines.push(format!("Transfinite Volume{{{}}};", structured.tag));
+                }
+            }
+            if structured.recombine {
+                recombine_lines.push(format!("Recombine Surface{{{}}};", surfaces));
+            }
+        }
+
+        [
+            list_lines,
+            curve_lines,
+            surface_lines,
+            recombine_lines,
+            volume_lines,
+        ]
+        .concat()
+    }
+
+    fn field_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .fields
+            .iter()
+            .flat_map(MeshField::script_lines)
+            .collect();
+        if let Some(background_field) = self.background_field {
+            lines.push(format!("Background Field = {};", background_field));
+        }
+        lines
+    }
+
+    //the layer field takes the id after the mesh size fields
+    fn boundary_layer_lines(&self) -> Vec<String> {
+        let field_id = self.fields.iter().map(|field| field.id).max().unwrap_or(0) + 1;
+        self.boundary_layer.script_lines(
+            &self.boundary_layer_walls(),
+            field_id,
+            self.mesh_paras.get("Mesh.MeshSizeMax"),
+        )
+    }
+
+    ///curves of the groups marked as walls and the single wall curves
+    pub fn boundary_layer_walls(&self) -> IdSet {
+        let mut walls = self.boundary_layer.wall_curves.clone();
+        for curve_phys in &self.curve_phy_list {
+            if curve_phys.boundary_layer {
+                for curve_id in curve_phys.curve_ids.iter() {
+                    walls.insert(curve_id);
+                }
+            }
+        }
+        walls
+    }
+
+    ///Auto before the entities are queried, scripts would guess 3D
+    pub fn dimension_pending(&self) -> bool {
+        self.model_dimension == ModelDimension::Auto && self.detected_dimension.is_none()
+    }
+
+    ///the mesh dimension, None while it is pending
+    pub fn known_mesh_dimension(&self) -> Option<u8> {
+        (!self.dimension_pending()).then(|| self.mesh_dimension())
+    }
+
+    ///3 or 2, Auto falls back to 3 until the entities are queried
+    pub fn mesh_dimension(&self) -> u8 {
+        match self.model_dimension {
+            ModelDimension::Auto => self.detected_dimension.unwrap_or(3),
+            ModelDimension::Solid => 3,
+            ModelDimension::Shell => 2,
+        }
+    }
+
+    ///boundary layers are grown in the surface mesh, Gmsh has no layer field for volumes
+    pub fn check_boundary_layer(&self) -> Result<()> {
+        if self.mesh_dimension() == 3 && !self.boundary_layer_walls().is_empty() {
+            return Err(TermshError::Input(String::from(
+                "boundary layers need a 2D model, remove the walls or set the model dimension to 2",
+            )));
+        }
+        self.boundary_layer.check(&self.boundary_layer_walls())
+    }
 
+    ///(dim, index, name) of the surface, curve and point groups, which carry boundary conditions
+    pub fn condition_groups(&self) -> Vec<(u8, usize, &str)> {
+        let surfaces = self.surf_phy_list.iter().map(|phys| phys.name.as_str());
+        let curves = self.curve_phy_list.iter().map(|phys| phys.name.as_str());
+        let points = self.point_phy_list.iter().map(|phys| phys.name.as_str());
+
+        let mut groups = Vec::new();
+        for (dim, names) in [
+            (2, surfaces.collect::<Vec<_>>()),
+            (1, curves.collect()),
+            (0, points.collect()),
+        ] {
+            for (idx, name) in names.into_iter().enumerate() {
+                groups.push((dim, idx, name));
+            }
+        }
+        groups
+    }
+
+    pub fn group_conditions(&self, dim: u8, idx: usize) -> Option<&Vec<BoundaryCondition>> {
+        match dim {
+            2 => self
+                .surf_phy_list
+                .get(idx)
+                .map(|phys| &phys.boundary_conditions),
+            1 => self
+                .curve_phy_list
+                .get(idx)
+                .map(|phys| &phys.boundary_conditions),
+            0 => self
+                .point_phy_list
+                .get(idx)
+                .map(|phys| &phys.boundary_conditions),
+            _ => None,
+        }
+    }
+
+    pub fn group_conditions_mut(
+        &mut self,
+        dim: u8,
+        idx: usize,
+    ) -> Option<&mut Vec<BoundaryCondition>> {
+        match dim {
+            2 => self
+                .surf_phy_list
+                .get_mut(idx)
+                .map(|phys| &mut phys.boundary_conditions),
+            1 => self
+                .curve_phy_list
+                .get_mut(idx)
+                .map(|phys| &mut phys.boundary_conditions),
+            0 => self
+                .point_phy_list
+                .get_mut(idx)
+                .map(|phys| &mut phys.boundary_conditions),
+            _ => None,
+        }
+    }
+
+    ///Refuse boundary conditions with values which are not numbers of their quantity
+    ///or on groups they do not apply to, eg. a pressure on a point group or on curves of a 3D model
+    pub fn check_conditions(&self) -> Result<()> {
+        for (dim, idx, name) in self.condition_groups() {
+            for condition in self.group_conditions(dim, idx).into_iter().flatten() {
+                condition
+                    .kind
+                    .check_dim(dim, self.known_mesh_dimension())
+                    .map_err(|e| TermshError::Input(format!("group {}: {}", name, e)))?;
+                condition
+                    .check(self.units.target)
+                    .map_err(|e| TermshError::Input(format!("group {}: {}", name, e)))?;
+            }
+        }
+        Ok(())
+    }
+
+    ///Refuse volume groups using materials the library does not have
+    pub fn check_materials(&self, library: &MaterialLibrary) -> Result<()> {
         for vol_phys in &self.vol_phy_list {
-            if vol_phys.vol_ids.is_empty() {
-                continue; //skip empty content
+            if !vol_phys.material.is_empty() && library.get(&vol_phys.material).is_none() {
+                return Err(TermshError::Input(format!(
+                    "Physical Volume {} uses the unknown material \"{}\"",
+                    vol_phys.name, vol_phys.material
+                )));
             }
-            scirpt_content += &format!(
-                "Physical Volume(\"{}\",{})={{{}}};\n",
-                vol_phys.name, vol_phys.phys_id, vol_phys.vol_ids
-            );
         }
+        for surf_phys in &self.surf_phy_list {
+            if !surf_phys.material.is_empty() && library.get(&surf_phys.material).is_none() {
+                return Err(TermshError::Input(format!(
+                    "Physical Surface {} uses the unknown material \"{}\"",
+                    surf_phys.name, surf_phys.material
+                )));
+            }
+        }
+        Ok(())
+    }
 
-        //generate Physical Surface scripts
-        scirpt_content += &format!("/* Physical Surface Grouping */\n");
+    ///Nastran decks are only written when their elements can be given properties:
+    ///physical ids as element tags, numeric ids and a material on the meshed groups,
+    ///a thickness on shells, numeric ids and entities on the groups carrying boundary conditions
+    ///and a reference temperature on the materials when there are temperature loads
+    ///the boundary conditions must pass check_conditions, invalid values would be written as 0
+    pub fn check_decks(&self, library: &MaterialLibrary) -> Result<()> {
+        let decks: Vec<&ExportSetting> = self
+            .enabled_exports()
+            .into_iter()
+            .filter(|setting| setting.format.is_nastran() && setting.nastran_deck)
+            .collect();
+        if decks.is_empty() {
+            return Ok(());
+        }
+        self.check_conditions()?;
 
-        for sur_phys in &self.surf_phy_list {
-            if sur_phys.surf_ids.is_empty() {
-                continue; //skip empty content
+        let refuse = |reason: String| Err(TermshError::Input(format!("Nastran deck: {}", reason)));
+        for setting in decks {
+            if setting.element_tag_type != 2 {
+                return refuse(format!(
+                    "{} needs the physical ids as element tags, set its Tag Type to 2",
+                    setting.format.label()
+                ));
             }
-            scirpt_content += &format!(
-                "Physical Surface(\"{}\",{})={{{}}};\n",
-                sur_phys.name, sur_phys.phys_id, sur_phys.surf_ids
-            );
         }
 
-        //generate Mesh Parameter scripts
-        scirpt_content += &format!("/* Mesh Setting */\n");
+        let numeric_id = |kind: &str, name: &str, phys_id: &str| {
+            if phys_id.parse::<u32>().is_ok() {
+                Ok(())
+            } else {
+                refuse(format!(
+                    "Physical {} {} needs a numeric Physical ID",
+                    kind, name
+                ))
+            }
+        };
+        let mut materials = Vec::new();
+        if self.mesh_dimension() == 3 {
+            for vol_phys in &self.vol_phy_list {
+                numeric_id("Volume", &vol_phys.name, &vol_phys.phys_id)?;
+                if vol_phys.material.is_empty() {
+                    return refuse(format!("Physical Volume {} has no material", vol_phys.name));
+                }
+                materials.push(&vol_phys.material);
+            }
+        } else {
+            for surf_phys in &self.surf_phy_list {
+                numeric_id("Surface", &surf_phys.name, &surf_phys.phys_id)?;
+                if surf_phys.material.is_empty() {
+                    return refuse(format!(
+                        "Physical Surface {} has no material",
+                        surf_phys.name
+                    ));
+                }
+                if !surf_phys.thickness.parse::<f64>().is_ok_and(|t| t > 0.0) {
+                    return refuse(format!(
+                        "Physical Surface {} needs a positive shell thickness",
+                        surf_phys.name
+                    ));
+                }
+                materials.push(&surf_phys.material);
+            }
+        }
 
-        if !self.mesh_paras.max_size.is_empty() {
-            scirpt_content += &format!("Mesh.MeshSizeMax={};\n", self.mesh_paras.max_size);
+        let condition_groups = self
+            .surf_phy_list
+            .iter()
+            .map(|phys| {
+                (
+                    "Surface",
+                    &phys.name,
+                    &phys.phys_id,
+                    &phys.surf_ids,
+                    &phys.boundary_conditions,
+                )
+            })
+            .chain(self.curve_phy_list.iter().map(|phys| {
+                (
+                    "Curve",
+                    &phys.name,
+                    &phys.phys_id,
+                    &phys.curve_ids,
+                    &phys.boundary_conditions,
+                )
+            }))
+            .chain(self.point_phy_list.iter().map(|phys| {
+                (
+                    "Point",
+                    &phys.name,
+                    &phys.phys_id,
+                    &phys.point_ids,
+                    &phys.boundary_conditions,
+                )
+            }));
+        let mut has_temperature = false;
+        for (kind, name, phys_id, ids, conditions) in condition_groups {
+            if conditions.is_empty() {
+                continue;
+            }
+            numeric_id(kind, name, phys_id)?;
+            //check_dim lets them act on the edges of the shells, PLOAD4 loads faces only
+            if kind == "Curve"
+                && self.mesh_dimension() == 2
+                && let Some(condition) = conditions
+                    .iter()
+                    .find(|condition| matches!(condition.kind, BcKind::Pressure | BcKind::Traction))
+            {
+                return refuse(format!(
+                    "{} on Physical Curve {} is an edge load, which is not written, use a Force instead",
+                    condition.kind, name
+                ));
+            }
+            if ids.is_empty() {
+                return refuse(format!(
+                    "Physical {} {} has boundary conditions but no {}s",
+                    kind,
+                    name,
+                    kind.to_lowercase()
+                ));
+            }
+            has_temperature |= conditions
+                .iter()
+                .any(|condition| condition.kind == BcKind::Temperature);
         }
+        self.check_materials(library)?;
 
-        //perform meshing scripts
-        scirpt_content += &format!("Mesh 3;\n");
+        //TEMP loads are measured from the TREF of the materials
+        if has_temperature {
+            for material in materials.into_iter().filter_map(|name| library.get(name)) {
+                if material.reference_temperature.is_none() {
+                    return refuse(format!(
+                        "temperature loads need the reference temperature of {}, set its reference_temperature in {}",
+                        material.name, MATERIAL_FILE
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 
-        //get filename_prefix
-        let filename_prefix = Path::new(&self.geometry_file)
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap();
+    ///Refuse fields referring to fields or surfaces which do not exist
+    ///entities are only checked when the entities are given
+    pub fn check_fields(&self, entity_list: Option<&EntityList>) -> Result<()> {
+        let problems = field_problems(&self.fields, self.background_field, entity_list);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(TermshError::Input(format!(
+                "invalid mesh size fields: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+
+    ///Refuse advanced options the TUI would not take, eg. from an edited project file
+    ///options outside the catalog need to be marked unchecked
+    pub fn check_advanced_options(&self) -> Result<()> {
+        for option in &self.advanced_options {
+            validate_advanced(&option.name, &option.value, option.unchecked)
+                .map_err(TermshError::Input)?;
+        }
+        Ok(())
+    }
+
+    fn meshing_lines(&self) -> Vec<String> {
+        vec![format!("Mesh {};", self.mesh_dimension())]
+    }
+
+    ///Log texts Gmsh prints once the script of the action has run through
+    pub fn done_markers(&self, action: ScriptAction) -> Vec<String> {
+        let mut markers = vec![format!("Done meshing {}D", self.mesh_dimension())];
+        if let ScriptAction::Export = action {
+            for setting in self.enabled_exports() {
+                markers.push(format!(
+                    "Done writing '{}'",
+                    self.output_file_name(setting.format)
+                ));
+            }
+        }
+        markers
+    }
+
+    //Gmsh writes no elements for points, the decks find their nodes by the coordinates
+    fn point_coordinate_lines(&self) -> Vec<String> {
+        let has_deck = self
+            .enabled_exports()
+            .iter()
+            .any(|setting| setting.format.is_nastran() && setting.nastran_deck);
+        if !has_deck {
+            return Vec::new();
+        }
 
-        //generate save to .nas file scripts
-        //Mesh.Format=31; Save the mesh in bdf format
-        //Mesh.BdfFieldFormat=1; Save in small format
+        let mut lines = Vec::new();
+        for point_phys in &self.point_phy_list {
+            if point_phys.boundary_conditions.is_empty() {
+                continue;
+            }
+            for point_id in point_phys.point_ids.iter() {
+                lines.push(format!("point_xyz[] = Point{{{}}};", point_id));
+                lines.push(format!(
+                    "Printf(\"POINT {} %.12g %.12g %.12g\", point_xyz[0], point_xyz[1], point_xyz[2]) >> \"{}\";",
+                    point_id,
+                    self.point_output_file_name()
+                ));
+            }
+        }
+        lines
+    }
+
+    fn export_lines(&self, setting: &ExportSetting) -> Vec<String> {
         //Mesh.SaveAll=0; Only save Physical Objects
         //Mesh.SaveElementTagType=2; Save the tag using Physical IDs
-        scirpt_content += &format!(
-            "Mesh.Format=31;\n\
-            Mesh.BdfFieldFormat=1;\n\
-            Mesh.SaveAll=0;\n\
-            Mesh.SaveElementTagType=2;\n\
-            Save \"{}\";",
-            filename_prefix.to_owned() + ".nas"
-        );
+        let mut lines = setting.format.format_lines();
+        lines.push(format!("Mesh.SaveAll={};", setting.save_all as u8));
+        lines.push(format!(
+            "Mesh.SaveElementTagType={};",
+            setting.element_tag_type
+        ));
+        lines.push(format!(
+            "Save \"{}\";",
+            self.output_file_name(setting.format)
+        ));
+
+        lines
+    }
+}
+
+///What the generated script is going to do after meshing
+#[derive(Debug, Clone, Copy)]
+pub enum ScriptAction {
+    Preview,       //mesh and show in Gmsh GUI
+    Export,        //mesh and save all enabled export formats
+    QueryEntities, //list volumes and surfaces of the healed geometry, no meshing
+}
+
+///A .geo script made of titled sections, rendered as one file
+#[derive(Debug, Clone, Default)]
+pub struct GeoScript {
+    sections: Vec<ScriptSection>,
+}
 
-        //export content to temporary script file
-        let temp_script_file_name = filename_prefix.to_owned() + "_temp.geo";
-        if let Err(err) = fs::write(&temp_script_file_name, scirpt_content) {
-            panic!("Error when writing to temporary script file: {}", err)
+#[derive(Debug, Clone)]
+pub struct ScriptSection {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl GeoScript {
+    pub fn new() -> Self {
+        GeoScript {
+            sections: Vec::new(),
         }
+    }
 
-        //spawn Gmsh Child Process
-        let gmsh_child_handle = std::process::Command::new("gmsh")
-            .arg(&temp_script_file_name)
-            .stderr(Stdio::null())
-            .stdout(Stdio::null())
-            .spawn()
-            .expect("failed to start gmsh");
+    pub fn push_section(&mut self, title: &str, lines: Vec<String>) {
+        self.sections.push(ScriptSection {
+            title: title.to_string(),
+            lines,
+        });
+    }
+
+    pub fn render(&self) -> String {
+        let mut script_content = String::new();
+
+        for section in &self.sections {
+            script_content += &format!("/* {} */\n", section.title);
+            for line in &section.lines {
+                script_content += line;
+                script_content += "\n";
+            }
+        }
+
+        script_content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (Ok(gmsh_child_handle), temp_script_file_name)
+    fn size_lines(script: &GeoScript) -> Vec<String> {
+        script
+            .render()
+            .lines()
+            .filter(|line| line.starts_with("MeshSize"))
+            .map(String::from)
+            .collect()
     }
 
-    pub fn save_cache(&self) {
-        //Save PhysPairList to termsh_cache.json when existing the program
-        let mut phys_pair_list = PhysPairList {
-            vol_pairs: Vec::new(),
-            sur_pairs: Vec::new(),
+    #[test]
+    fn entity_sizes_win_over_groups_and_surfaces_over_volumes() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        gmsh_para.vol_phy_list.push(VolPhys {
+            name: String::from("body"),
+            phys_id: String::from("1"),
+            vol_ids: "1:2".parse().unwrap(),
+            mesh_size: String::from("4"),
+            material: String::new(),
+        });
+        gmsh_para.surf_phy_list.push(SurfPhys {
+            name: String::from("inlet"),
+            phys_id: String::from("2"),
+            surf_ids: "3".parse().unwrap(),
+            mesh_size: String::from("1"),
+            boundary_conditions: Vec::new(),
+            material: String::new(),
+            thickness: String::new(),
+        });
+        //entered before the volume, still written after it
+        gmsh_para.set_entity_mesh_size(2, 5, String::from("0.5"));
+        gmsh_para.set_entity_mesh_size(3, 2, String::from("2"));
+
+        assert_eq!(
+            size_lines(&gmsh_para.build_script(ScriptAction::Preview)),
+            [
+                "MeshSize{ PointsOf{ Volume{1:2}; } } = 4;",
+                "MeshSize{ PointsOf{ Volume{2}; } } = 2;",
+                "MeshSize{ PointsOf{ Surface{3}; } } = 1;",
+                "MeshSize{ PointsOf{ Surface{5}; } } = 0.5;",
+            ]
+        );
+    }
+
+    #[test]
+    fn boundary_layers_are_grown_in_2d_only() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        gmsh_para.curve_phy_list.push(CurvPhys {
+            name: String::from("wall"),
+            phys_id: String::from("3"),
+            curve_ids: "1:2".parse().unwrap(),
+            boundary_layer: true,
+            boundary_conditions: Vec::new(),
+        });
+        gmsh_para.boundary_layer = BoundaryLayer {
+            wall_curves: "5".parse().unwrap(),
+            first_thickness: String::from("0.1"),
+            growth_ratio: String::from("2"),
+            layer_count: String::from("3"),
         };
 
-        for vol_phys in self.vol_phy_list.clone() {
-            phys_pair_list.vol_pairs.push(PhysPair {
-                name: vol_phys.name,
-                phys_id: vol_phys.phys_id,
+        //Gmsh has no layer field for volumes
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        assert!(gmsh_para.check_boundary_layer().is_err());
+
+        gmsh_para.model_dimension = ModelDimension::Shell;
+        assert!(gmsh_para.check_boundary_layer().is_ok());
+        let script = gmsh_para.build_script(ScriptAction::Preview).render();
+        for line in [
+            "Field[1] = BoundaryLayer;",
+            "Field[1].CurvesList = {1:2, 5};",
+            "Field[1].Thickness = 0.7;",
+            "BoundaryLayer Field = 1;",
+        ] {
+            assert!(script.contains(line), "{} missing", line);
+        }
+        assert!(!script.contains("Extrude"));
+
+        gmsh_para.boundary_layer.layer_count = String::from("0");
+        assert!(gmsh_para.check_boundary_layer().is_err());
+    }
+
+    #[test]
+    fn structured_surface_and_volume() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        for (dim, tag, nodes) in [(2, 5, "10"), (3, 1, "6")] {
+            gmsh_para.structured_entities.push(StructuredEntity {
+                dim,
+                tag,
+                nodes: String::from(nodes),
+                recombine: true,
             });
         }
+        //quadrangles only, no transfinite curves
+        gmsh_para.structured_entities.push(StructuredEntity {
+            dim: 2,
+            tag: 8,
+            nodes: String::new(),
+            recombine: true,
+        });
 
-        for sur_phys in self.surf_phy_list.clone() {
-            phys_pair_list.sur_pairs.push(PhysPair {
-                name: sur_phys.name,
-                phys_id: sur_phys.phys_id,
+        assert_eq!(
+            gmsh_para.structured_lines(),
+            [
+                "tf_c_s5[] = Unique(Abs(Boundary{ Surface{5}; }));",
+                "tf_s_v1[] = Abs(Boundary{ Volume{1}; });",
+                "tf_c_v1[] = Unique(Abs(Boundary{ Surface{tf_s_v1[]}; }));",
+                "Transfinite Curve{tf_c_s5[]} = 10;",
+                "Transfinite Curve{tf_c_v1[]} = 6;",
+                "Transfinite Surface{5};",
+                "Transfinite Surface{tf_s_v1[]};",
+                "Recombine Surface{5};",
+                "Recombine Surface{tf_s_v1[]};",
+                "Recombine Surface{8};",
+                "Transfinite Volume{1};",
+            ]
+        );
+    }
+
+    #[test]
+    fn curve_and_point_groups() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        for (name, phys_id, curve_ids) in [("edge", "4", "7, 1:2"), ("empty", "5", "")] {
+            gmsh_para.curve_phy_list.push(CurvPhys {
+                name: String::from(name),
+                phys_id: String::from(phys_id),
+                curve_ids: curve_ids.parse().unwrap(),
+                boundary_layer: false,
+                boundary_conditions: Vec::new(),
+            });
+        }
+        for (name, phys_id, point_ids) in [("tip", "9", "3"), ("empty", "10", "")] {
+            gmsh_para.point_phy_list.push(PointPhys {
+                name: String::from(name),
+                phys_id: String::from(phys_id),
+                point_ids: point_ids.parse().unwrap(),
+                boundary_conditions: Vec::new(),
             });
         }
 
-        let json_str = serde_json::to_string_pretty(&phys_pair_list).expect("Error Serialize");
-        if let Err(e) = fs::write("termsh_cache.json", json_str) {
-            panic!("Error writing to cache: {}", e)
+        //groups without ids are left out
+        assert_eq!(
+            gmsh_para.phys_curve_lines(),
+            ["Physical Curve(\"edge\",4)={1:2, 7};"]
+        );
+        assert_eq!(
+            gmsh_para.phys_point_lines(),
+            ["Physical Point(\"tip\",9)={3};"]
+        );
+        let script = gmsh_para.build_script(ScriptAction::Preview).render();
+        assert!(
+            script
+                .contains("/* Physical Curve Grouping */\nPhysical Curve(\"edge\",4)={1:2, 7};\n")
+        );
+        assert!(script.contains("/* Physical Point Grouping */\nPhysical Point(\"tip\",9)={3};\n"));
+    }
+
+    #[test]
+    fn meshing_follows_the_model_dimension() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        let meshing = |gmsh_para: &GmshPara| gmsh_para.meshing_lines();
+
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        assert_eq!(meshing(&gmsh_para), ["Mesh 3;"]);
+        gmsh_para.model_dimension = ModelDimension::Shell;
+        assert_eq!(meshing(&gmsh_para), ["Mesh 2;"]);
+        assert_eq!(
+            gmsh_para.done_markers(ScriptAction::Preview),
+            ["Done meshing 2D"]
+        );
+
+        //Auto takes the dimension of the queried entities
+        gmsh_para.model_dimension = ModelDimension::Auto;
+        gmsh_para.detected_dimension = Some(2);
+        assert!(!gmsh_para.dimension_pending());
+        assert_eq!(gmsh_para.known_mesh_dimension(), Some(2));
+        assert_eq!(meshing(&gmsh_para), ["Mesh 2;"]);
+    }
+
+    #[test]
+    fn pending_dimension_is_not_exported() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        gmsh_para.model_dimension = ModelDimension::Auto;
+
+        //scripts before the query fall back to 3D
+        assert!(gmsh_para.dimension_pending());
+        assert_eq!(gmsh_para.known_mesh_dimension(), None);
+        assert_eq!(gmsh_para.meshing_lines(), ["Mesh 3;"]);
+
+        let error = gmsh_para
+            .write_script(ScriptAction::Export)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("not detected yet"), "{}", error);
+    }
+
+    //the lines of each section, by title
+    fn section_lines<'a>(script: &'a GeoScript, title: &str) -> Option<&'a [String]> {
+        script
+            .sections
+            .iter()
+            .find(|section| section.title == title)
+            .map(|section| section.lines.as_slice())
+    }
+
+    #[test]
+    fn healing_options_before_the_import_and_healing_after_it() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        gmsh_para.healing_paras = HealingPara {
+            boolean_fragments: true,
+            coherence: false,
+            tolerance: String::from("1e-4"),
+            fix_degenerated: true,
+            fix_small_edges: false,
+            fix_small_faces: true,
+            sew_faces: false,
+            make_solids: true,
+        };
+
+        let script = gmsh_para.build_script(ScriptAction::Preview);
+        assert_eq!(
+            section_lines(&script, "Healing Options").unwrap(),
+            [
+                "Geometry.Tolerance=1e-4;",
+                "Geometry.OCCFixDegenerated=1;",
+                "Geometry.OCCFixSmallFaces=1;",
+                "Geometry.OCCMakeSolids=1;",
+            ]
+        );
+        assert_eq!(
+            section_lines(&script, "Heal the CAD geometry").unwrap(),
+            ["BooleanFragments{ Volume{:}; Surface {:}; Delete; }{}"]
+        );
+        let titles: Vec<&str> = script
+            .sections
+            .iter()
+            .map(|section| section.title.as_str())
+            .collect();
+        let position = |title: &str| titles.iter().position(|other| *other == title).unwrap();
+        assert!(position("Healing Options") < position("Import Geometry"));
+        assert!(position("Import Geometry") < position("Heal the CAD geometry"));
+
+        //the defaults leave the Gmsh options alone
+        gmsh_para.healing_paras = HealingPara::default();
+        let script = gmsh_para.build_script(ScriptAction::Preview);
+        assert!(
+            section_lines(&script, "Healing Options")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            section_lines(&script, "Heal the CAD geometry").unwrap(),
+            [
+                "BooleanFragments{ Volume{:}; Surface {:}; Delete; }{}",
+                "Coherence;",
+            ]
+        );
+
+        //a .geo script is not healed
+        gmsh_para.geometry_parts[0] = GeometryPart::new(String::from("a.geo"));
+        let script = gmsh_para.build_script(ScriptAction::Preview);
+        assert!(section_lines(&script, "Heal the CAD geometry").is_none());
+    }
+
+    #[test]
+    fn export_lines_of_each_format() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("parts/bracket.step")));
+
+        for (format, format_lines, file) in [
+            (
+                ExportFormat::Nas,
+                &["Mesh.Format=31;", "Mesh.BdfFieldFormat=1;"][..],
+                "bracket.nas",
+            ),
+            (
+                ExportFormat::NasLarge,
+                &["Mesh.Format=31;", "Mesh.BdfFieldFormat=2;"],
+                "bracket_large.nas",
+            ),
+            (
+                ExportFormat::NasFree,
+                &["Mesh.Format=31;", "Mesh.BdfFieldFormat=0;"],
+                "bracket_free.nas",
+            ),
+            (
+                ExportFormat::Msh22,
+                &["Mesh.Format=1;", "Mesh.MshFileVersion=2.2;"],
+                "bracket_v22.msh",
+            ),
+            (
+                ExportFormat::Msh41,
+                &["Mesh.Format=1;", "Mesh.MshFileVersion=4.1;"],
+                "bracket.msh",
+            ),
+            (ExportFormat::Inp, &["Mesh.Format=39;"], "bracket.inp"),
+            (ExportFormat::Unv, &["Mesh.Format=2;"], "bracket.unv"),
+            (ExportFormat::Vtk, &["Mesh.Format=16;"], "bracket.vtk"),
+            (ExportFormat::Med, &["Mesh.Format=33;"], "bracket.med"),
+        ] {
+            assert_eq!(format.format_lines(), format_lines, "{:?}", format);
+            assert_eq!(gmsh_para.output_file_name(format), file);
         }
+
+        let mut setting = ExportSetting::new(ExportFormat::Inp);
+        setting.save_all = true;
+        setting.element_tag_type = 1;
+        assert_eq!(
+            gmsh_para.export_lines(&setting),
+            [
+                "Mesh.Format=39;",
+                "Mesh.SaveAll=1;",
+                "Mesh.SaveElementTagType=1;",
+                "Save \"bracket.inp\";",
+            ]
+        );
+
+        //only the enabled formats are saved, Nas by default
+        let script = gmsh_para.build_script(ScriptAction::Export);
+        let titles: Vec<&str> = script
+            .sections
+            .iter()
+            .map(|section| section.title.as_str())
+            .filter(|title| title.starts_with("Export"))
+            .collect();
+        assert_eq!(titles, [format!("Export {}", ExportFormat::Nas.label())]);
+        assert_eq!(
+            section_lines(&script, titles[0]).unwrap(),
+            [
+                "Mesh.Format=31;",
+                "Mesh.BdfFieldFormat=1;",
+                "Mesh.SaveAll=0;",
+                "Mesh.SaveElementTagType=2;",
+                "Save \"bracket.nas\";",
+            ]
+        );
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct PhysPair {
-    name: String,
-    phys_id: String,
-}
+    #[test]
+    fn sections_are_in_script_order() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .geometry_parts
+            .push(GeometryPart::new(String::from("a.step")));
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        let titles = |action: ScriptAction| -> Vec<String> {
+            gmsh_para
+                .build_script(action)
+                .sections
+                .into_iter()
+                .map(|section| section.title)
+                .collect()
+        };
+
+        let meshing = [
+            "General Options",
+            "Healing Options",
+            "Import Geometry",
+            "Heal the CAD geometry",
+            "Physical Volume Grouping",
+            "Physical Surface Grouping",
+            "Physical Curve Grouping",
+            "Physical Point Grouping",
+            "Mesh Setting",
+            "Local Mesh Size",
+            "Mesh Size Fields",
+            "Boundary Layers",
+            "Structured Mesh",
+            "Meshing",
+        ];
+        assert_eq!(titles(ScriptAction::Preview), meshing);
+
+        let mut export = meshing.map(String::from).to_vec();
+        export.push(String::from("Point Coordinates"));
+        export.push(format!("Export {}", ExportFormat::Nas.label()));
+        assert_eq!(titles(ScriptAction::Export), export);
 
-//use serde to serial/deserial to termsh_cache.json
-#[derive(Serialize, Deserialize)]
-struct PhysPairList {
-    vol_pairs: Vec<PhysPair>,
-    sur_pairs: Vec<PhysPair>,
+        //the parts are located before the healing merges them
+        assert_eq!(
+            titles(ScriptAction::QueryEntities),
+            [
+                "General Options",
+                "Healing Options",
+                "Import Geometry",
+                "Query Parts",
+                "Heal the CAD geometry",
+                "Query Entities",
+            ]
+        );
+    }
 }
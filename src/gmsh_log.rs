@@ -0,0 +1,190 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Output},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Info,
+    Warning,
+    Error,
+    Other, //anything Gmsh prints without a level, eg. progress
+}
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub text: String,
+}
+
+impl LogLine {
+    ///Gmsh prefixes its messages with "Info    : ", "Warning : " or "Error   : "
+    pub fn parse(line: &str) -> Self {
+        let level = match line.split_once(':') {
+            Some((prefix, _)) => match prefix.trim() {
+                "Info" => LogLevel::Info,
+                "Warning" => LogLevel::Warning,
+                "Error" => LogLevel::Error,
+                _ => LogLevel::Other,
+            },
+            None => LogLevel::Other,
+        };
+
+        LogLine {
+            level,
+            text: line.to_string(),
+        }
+    }
+}
+
+///Output of one Gmsh run, filled by reader threads while Gmsh is running
+#[derive(Debug, Clone, Default)]
+pub struct GmshLog {
+    lines: Arc<Mutex<Vec<LogLine>>>,
+}
+
+impl GmshLog {
+    pub fn new() -> Self {
+        GmshLog {
+            lines: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    ///Take the piped stdout and stderr of the child and collect them in background
+    pub fn capture(child: &mut Child) -> Self {
+        let log = GmshLog::new();
+
+        if let Some(stdout) = child.stdout.take() {
+            log.read_in_background(stdout);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            log.read_in_background(stderr);
+        }
+
+        log
+    }
+
+    ///Collect the output of a Gmsh run that already finished
+    pub fn from_output(output: &Output) -> Self {
+        let log = GmshLog::new();
+
+        for stream in [&output.stdout, &output.stderr] {
+            for line in String::from_utf8_lossy(stream).lines() {
+                log.push_line(line);
+            }
+        }
+
+        log
+    }
+
+    fn read_in_background(&self, stream: impl Read + Send + 'static) {
+        let log = self.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                match line {
+                    Ok(line) => log.push_line(&line),
+                    Err(_) => break, //pipe closed, Gmsh has exited
+                }
+            }
+        });
+    }
+
+    pub fn push_line(&self, line: &str) {
+        self.lines.lock().unwrap().push(LogLine::parse(line));
+    }
+
+    pub fn lines(&self) -> Vec<LogLine> {
+        self.lines.lock().unwrap().clone()
+    }
+
+    pub fn count(&self, level: LogLevel) -> usize {
+        self.lines
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| line.level == level)
+            .count()
+    }
+
+    pub fn contains(&self, text: &str) -> bool {
+        self.lines
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|line| line.text.contains(text))
+    }
+
+    ///Why the run should be treated as failed: the first Error, or a mesh without elements
+    pub fn failure(&self) -> Option<String> {
+        let lines = self.lines.lock().unwrap();
+
+        if let Some(error) = lines.iter().find(|line| line.level == LogLevel::Error) {
+            return Some(error.text.clone());
+        }
+
+        //after meshing Gmsh reports "Info    : 1234 nodes 5678 elements"
+        let empty_mesh = lines.iter().any(|line| {
+            let words: Vec<&str> = line.text.split_whitespace().collect();
+            words.ends_with(&["0", "elements"]) && words.contains(&"nodes")
+        });
+        if empty_mesh {
+            return Some(String::from("the mesh is empty"));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(lines: &[&str]) -> GmshLog {
+        let log = GmshLog::new();
+        for line in lines {
+            log.push_line(line);
+        }
+        log
+    }
+
+    #[test]
+    fn levels_from_the_prefix() {
+        let level = |line| LogLine::parse(line).level;
+        assert_eq!(level("Info    : Meshing 1D..."), LogLevel::Info);
+        assert_eq!(level("Warning : Unknown entity 5"), LogLevel::Warning);
+        assert_eq!(level("Error   : Invalid boundary mesh"), LogLevel::Error);
+        assert_eq!(level("[ 40%] Meshing curve 3"), LogLevel::Other);
+        assert_eq!(level("Progress: 10"), LogLevel::Other);
+        assert_eq!(LogLine::parse("Error   : x").text, "Error   : x");
+    }
+
+    #[test]
+    fn first_error_is_the_failure() {
+        let log = log(&[
+            "Info    : Reading 'a.geo'...",
+            "Warning : Curve 3 is degenerated",
+            "Error   : Unknown volume 7",
+            "Error   : Syntax error",
+        ]);
+        assert_eq!(log.count(LogLevel::Error), 2);
+        assert_eq!(log.count(LogLevel::Warning), 1);
+        assert_eq!(
+            log.failure(),
+            Some(String::from("Error   : Unknown volume 7"))
+        );
+    }
+
+    #[test]
+    fn empty_mesh_is_a_failure() {
+        let empty = log(&["Info    : Done meshing 3D", "Info    : 0 nodes 0 elements"]);
+        assert_eq!(empty.failure(), Some(String::from("the mesh is empty")));
+
+        let meshed = log(&[
+            "Warning : Curve 3 is degenerated",
+            "Info    : 120 nodes 510 elements",
+        ]);
+        assert_eq!(meshed.failure(), None);
+    }
+}
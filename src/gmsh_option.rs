@@ -0,0 +1,662 @@
+///What values a Gmsh option takes
+#[derive(Debug, Clone, Copy)]
+pub enum OptionKind {
+    Length, //positive number, in the unit of the model
+    Number, //any number, eg. a factor or a threshold
+    Integer { min: i64, max: i64 },
+    Bool,
+    Choice(&'static [(&'static str, &'static str)]), //(value, meaning)
+}
+
+///A Gmsh option termsh knows how to check and explain
+#[derive(Debug, Clone, Copy)]
+pub struct GmshOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+    pub default: &'static str, //what Gmsh uses when termsh does not set it
+    pub description: &'static str,
+}
+
+const BOOL_CHOICES: &[(&str, &str)] = &[("0", "off"), ("1", "on")];
+
+impl GmshOption {
+    ///the name without the "Mesh." prefix, for tables
+    pub fn short_name(&self) -> &'static str {
+        self.name
+            .split_once('.')
+            .map_or(self.name, |(_, name)| name)
+    }
+
+    ///values to pick from, empty when the value is typed in
+    pub fn choices(&self) -> &'static [(&'static str, &'static str)] {
+        match self.kind {
+            OptionKind::Bool => BOOL_CHOICES,
+            OptionKind::Choice(choices) => choices,
+            _ => &[],
+        }
+    }
+
+    ///"6 (Frontal-Delaunay)" for choices, the plain value otherwise
+    pub fn describe_value(&self, value: &str) -> String {
+        match self.choices().iter().find(|(choice, _)| *choice == value) {
+            Some((choice, meaning)) => format!("{} ({})", choice, meaning),
+            None => value.to_string(),
+        }
+    }
+
+    ///Check a value typed by the user, the error tells what is allowed
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let valid = match self.kind {
+            OptionKind::Length => value.parse::<f64>().is_ok_and(|v| v > 0.0),
+            OptionKind::Number => value.parse::<f64>().is_ok(),
+            OptionKind::Integer { min, max } => {
+                value.parse::<i64>().is_ok_and(|v| (min..=max).contains(&v))
+            }
+            OptionKind::Bool | OptionKind::Choice(_) => {
+                self.choices().iter().any(|(choice, _)| *choice == value)
+            }
+        };
+
+        if valid {
+            return Ok(());
+        }
+
+        let allowed = match self.kind {
+            OptionKind::Length => String::from("a positive number"),
+            OptionKind::Number => String::from("a number"),
+            OptionKind::Integer { min, max } => format!("an integer from {} to {}", min, max),
+            OptionKind::Bool | OptionKind::Choice(_) => {
+                let values: Vec<&str> = self.choices().iter().map(|(choice, _)| *choice).collect();
+                format!("one of {}", values.join(", "))
+            }
+        };
+        Err(format!(
+            "{}: \"{}\" is not valid, expected {}",
+            self.short_name(),
+            value,
+            allowed
+        ))
+    }
+}
+
+///Options of the Mesh Parameters table, in table order
+pub const MESH_OPTIONS: [GmshOption; 12] = [
+    GmshOption {
+        name: "Mesh.MeshSizeMax",
+        kind: OptionKind::Length,
+        default: "1e+22",
+        description: "Largest element size",
+    },
+    GmshOption {
+        name: "Mesh.MeshSizeMin",
+        kind: OptionKind::Length,
+        default: "0",
+        description: "Smallest element size",
+    },
+    GmshOption {
+        name: "Mesh.MeshSizeFromCurvature",
+        kind: OptionKind::Integer { min: 0, max: 1000 },
+        default: "0",
+        description: "Elements per 2*Pi radians of curvature, 0 to disable",
+    },
+    GmshOption {
+        name: "Mesh.Algorithm",
+        kind: OptionKind::Choice(&[
+            ("1", "MeshAdapt"),
+            ("2", "Automatic"),
+            ("3", "Initial mesh only"),
+            ("5", "Delaunay"),
+            ("6", "Frontal-Delaunay"),
+            ("7", "BAMG"),
+            ("8", "Frontal-Delaunay for Quads"),
+            ("9", "Packing of Parallelograms"),
+            ("11", "Quasi-structured Quad"),
+        ]),
+        default: "6",
+        description: "2D mesh algorithm, used for the surfaces",
+    },
+    GmshOption {
+        name: "Mesh.Algorithm3D",
+        kind: OptionKind::Choice(&[
+            ("1", "Delaunay"),
+            ("3", "Initial mesh only"),
+            ("4", "Frontal"),
+            ("7", "MMG3D"),
+            ("9", "R-tree"),
+            ("10", "HXT"),
+        ]),
+        default: "1",
+        description: "3D mesh algorithm, used for the volumes",
+    },
+    GmshOption {
+        name: "Mesh.ElementOrder",
+        kind: OptionKind::Choice(&[("1", "Linear"), ("2", "Quadratic"), ("3", "Cubic")]),
+        default: "1",
+        description: "Order of the elements, eg. 2 for TET10",
+    },
+    GmshOption {
+        name: "Mesh.Optimize",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Optimize the quality of tetrahedra",
+    },
+    GmshOption {
+        name: "Mesh.OptimizeNetgen",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Optimize the quality of tetrahedra with Netgen",
+    },
+    GmshOption {
+        name: "Mesh.HighOrderOptimize",
+        kind: OptionKind::Choice(&[
+            ("0", "None"),
+            ("1", "Optimization"),
+            ("2", "Elastic and optimization"),
+            ("3", "Elastic"),
+            ("4", "Fast curving"),
+        ]),
+        default: "0",
+        description: "Untangle and improve curved high order elements",
+    },
+    GmshOption {
+        name: "Mesh.Smoothing",
+        kind: OptionKind::Integer { min: 0, max: 100 },
+        default: "1",
+        description: "Smoothing steps applied to the final mesh",
+    },
+    GmshOption {
+        name: "Mesh.RecombineAll",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Recombine triangles into quadrangles on all surfaces",
+    },
+    GmshOption {
+        name: "Mesh.SubdivisionAlgorithm",
+        kind: OptionKind::Choice(&[
+            ("0", "None"),
+            ("1", "All quadrangles"),
+            ("2", "All hexahedra"),
+            ("3", "Barycentric"),
+        ]),
+        default: "0",
+        description: "Split every element, eg. all tetrahedra into hexahedra",
+    },
+];
+
+//options with their own row in a table, not accepted as advanced options
+const GEOMETRY_TAB_OPTIONS: [&str; 7] = [
+    "Geometry.Tolerance",
+    "Geometry.OCCFixDegenerated",
+    "Geometry.OCCFixSmallEdges",
+    "Geometry.OCCFixSmallFaces",
+    "Geometry.OCCSewFaces",
+    "Geometry.OCCMakeSolids",
+    "Geometry.OCCTargetUnit",
+];
+
+//options rescaling the model or the saved mesh, they would leave the units of the Geometry tab,
+//the sizes and the Nastran deck behind, parts are scaled in the Parts tab instead
+const SCALING_OPTIONS: [&str; 3] = [
+    "Geometry.OCCScaling",
+    "Geometry.ScalingFactor",
+    "Mesh.ScalingFactor",
+];
+
+///Known Gmsh options which can be added in the Advanced tab, see the Gmsh reference manual
+pub const ADVANCED_OPTIONS: &[GmshOption] = &[
+    GmshOption {
+        name: "Mesh.MeshSizeFactor",
+        kind: OptionKind::Number,
+        default: "1",
+        description: "Factor applied to all mesh element sizes",
+    },
+    GmshOption {
+        name: "Mesh.MeshSizeFromPoints",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Compute element sizes from the values given at geometry points",
+    },
+    GmshOption {
+        name: "Mesh.MeshSizeFromParametricPoints",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Compute element sizes from the values given at parametric points",
+    },
+    GmshOption {
+        name: "Mesh.MeshSizeExtendFromBoundary",
+        kind: OptionKind::Choice(&[
+            ("0", "Never"),
+            ("1", "Surfaces and volumes"),
+            ("2", "Surfaces and volumes, smallest edge in 3D"),
+            ("-2", "Surfaces only"),
+            ("-3", "Volumes only"),
+        ]),
+        default: "1",
+        description: "Extend the element sizes from the boundaries into the interior",
+    },
+    GmshOption {
+        name: "Mesh.MinimumCirclePoints",
+        kind: OptionKind::Integer {
+            min: 0,
+            max: 100000,
+        },
+        default: "7",
+        description: "Minimum number of nodes used to mesh circles and ellipses",
+    },
+    GmshOption {
+        name: "Mesh.MinimumCurvePoints",
+        kind: OptionKind::Integer {
+            min: 0,
+            max: 100000,
+        },
+        default: "3",
+        description: "Minimum number of nodes used to mesh curves other than lines, circles and ellipses",
+    },
+    GmshOption {
+        name: "Mesh.MinimumElementsPerTwoPi",
+        kind: OptionKind::Integer {
+            min: 0,
+            max: 100000,
+        },
+        default: "6",
+        description: "Minimum number of elements per 2*Pi radians with MeshSizeFromCurvature",
+    },
+    GmshOption {
+        name: "Mesh.RecombinationAlgorithm",
+        kind: OptionKind::Choice(&[
+            ("0", "Simple"),
+            ("1", "Blossom"),
+            ("2", "Simple full-quad"),
+            ("3", "Blossom full-quad"),
+        ]),
+        default: "1",
+        description: "How triangles are recombined into quadrangles",
+    },
+    GmshOption {
+        name: "Mesh.SecondOrderLinear",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Place the mid-side nodes of high order elements on straight edges",
+    },
+    GmshOption {
+        name: "Mesh.SecondOrderIncomplete",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Create incomplete second order elements, eg. 20-node instead of 27-node hexahedra",
+    },
+    GmshOption {
+        name: "Mesh.HighOrderNumLayers",
+        kind: OptionKind::Integer { min: 0, max: 1000 },
+        default: "6",
+        description: "Layers of elements around a distorted element moved by HighOrderOptimize",
+    },
+    GmshOption {
+        name: "Mesh.HighOrderThresholdMin",
+        kind: OptionKind::Number,
+        default: "0.1",
+        description: "Minimum quality of high order elements before optimization",
+    },
+    GmshOption {
+        name: "Mesh.HighOrderThresholdMax",
+        kind: OptionKind::Number,
+        default: "2",
+        description: "Maximum quality of high order elements before optimization",
+    },
+    GmshOption {
+        name: "Mesh.OptimizeThreshold",
+        kind: OptionKind::Number,
+        default: "0.3",
+        description: "Optimize tetrahedra with a quality below this value",
+    },
+    GmshOption {
+        name: "Mesh.QualityType",
+        kind: OptionKind::Choice(&[("0", "SICN"), ("1", "SIGE"), ("2", "Gamma"), ("3", "Disto")]),
+        default: "2",
+        description: "Quality measure used by the optimizers",
+    },
+    GmshOption {
+        name: "Mesh.AllowSwapAngle",
+        kind: OptionKind::Number,
+        default: "10",
+        description: "Largest angle in degrees between two triangles for an edge swap",
+    },
+    GmshOption {
+        name: "Mesh.AngleToleranceFacetOverlap",
+        kind: OptionKind::Number,
+        default: "0.1",
+        description: "Angle in degrees below which two facets are taken as overlapping",
+    },
+    GmshOption {
+        name: "Mesh.RandomFactor",
+        kind: OptionKind::Number,
+        default: "1e-09",
+        description: "Random factor used by the 2D meshing algorithms",
+    },
+    GmshOption {
+        name: "Mesh.ToleranceInitialDelaunay",
+        kind: OptionKind::Number,
+        default: "1e-12",
+        description: "Tolerance of the initial 3D Delaunay mesh",
+    },
+    GmshOption {
+        name: "Mesh.MaxNumThreads2D",
+        kind: OptionKind::Integer { min: 0, max: 1024 },
+        default: "0",
+        description: "Threads used for 2D meshing, 0 for General.NumThreads",
+    },
+    GmshOption {
+        name: "Mesh.MaxNumThreads3D",
+        kind: OptionKind::Integer { min: 0, max: 1024 },
+        default: "0",
+        description: "Threads used for 3D meshing, 0 for General.NumThreads",
+    },
+    GmshOption {
+        name: "Mesh.Renumber",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Renumber nodes and elements in a continuous sequence after meshing",
+    },
+    GmshOption {
+        name: "Mesh.Binary",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Write the mesh in binary format when the format allows it",
+    },
+    GmshOption {
+        name: "Mesh.SaveParametric",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Save the parametric coordinates of the nodes",
+    },
+    GmshOption {
+        name: "Geometry.ToleranceBoolean",
+        kind: OptionKind::Number,
+        default: "0",
+        description: "Tolerance of the boolean operations, 0 to let OpenCASCADE decide",
+    },
+    GmshOption {
+        name: "Geometry.AutoCoherence",
+        kind: OptionKind::Choice(&[
+            ("0", "Off"),
+            ("1", "Remove duplicates"),
+            ("2", "Remove duplicates and fragment"),
+        ]),
+        default: "1",
+        description: "Remove duplicate entities after each geometry operation",
+    },
+    GmshOption {
+        name: "Geometry.OCCAutoFix",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Fix the orientation of wires, faces and shells built in OpenCASCADE",
+    },
+    GmshOption {
+        name: "Geometry.OCCBooleanPreserveNumbering",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Keep the tags of the entities unchanged by boolean operations",
+    },
+    GmshOption {
+        name: "Geometry.OCCImportLabels",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Import the labels and colors of STEP and IGES files",
+    },
+    GmshOption {
+        name: "Geometry.OCCParallel",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Run the OpenCASCADE boolean operations in parallel",
+    },
+    GmshOption {
+        name: "Geometry.OCCUnionUnify",
+        kind: OptionKind::Bool,
+        default: "1",
+        description: "Merge the faces and edges on the same surface or curve after a union",
+    },
+    GmshOption {
+        name: "General.NumThreads",
+        kind: OptionKind::Integer { min: 0, max: 1024 },
+        default: "1",
+        description: "Threads used by Gmsh, 0 for the system default",
+    },
+    GmshOption {
+        name: "General.Verbosity",
+        kind: OptionKind::Choice(&[("4", "Information"), ("5", "Status"), ("99", "Debug")]),
+        default: "5",
+        description: "How much Gmsh writes to the log, below 4 termsh cannot follow the progress",
+    },
+    GmshOption {
+        name: "General.ExpertMode",
+        kind: OptionKind::Bool,
+        default: "0",
+        description: "Do not warn about operations which can be slow",
+    },
+];
+
+///The catalog entry of an advanced option
+pub fn find_option(name: &str) -> Option<&'static GmshOption> {
+    ADVANCED_OPTIONS.iter().find(|option| option.name == name)
+}
+
+///Catalog options containing what was typed, ignoring case
+pub fn complete_option(typed: &str) -> Vec<&'static GmshOption> {
+    let typed = typed.trim().to_ascii_lowercase();
+    ADVANCED_OPTIONS
+        .iter()
+        .filter(|option| option.name.to_ascii_lowercase().contains(&typed))
+        .collect()
+}
+
+///Check an advanced option against the catalog, options owning a table row or rescaling the
+///model are refused. Options missing from the catalog are only passed to Gmsh when marked
+///unchecked, the warning says their value is not checked
+pub fn validate_advanced(
+    name: &str,
+    value: &str,
+    unchecked: bool,
+) -> Result<Option<String>, String> {
+    if MESH_OPTIONS.iter().any(|option| option.name == name) {
+        return Err(format!("{} is set in the Mesh tab", name));
+    }
+    if GEOMETRY_TAB_OPTIONS.contains(&name) {
+        return Err(format!("{} is set in the Geometry tab", name));
+    }
+    if SCALING_OPTIONS.contains(&name) {
+        return Err(format!(
+            "{} would rescale the model behind the units, scale the parts instead",
+            name
+        ));
+    }
+
+    if let Some(option) = find_option(name) {
+        return option.validate(value).map(|()| None);
+    }
+
+    let is_word = |part: &str| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    if !name
+        .split_once('.')
+        .is_some_and(|(category, option)| is_word(category) && is_word(option))
+    {
+        return Err(format!(
+            "\"{}\" is not a Gmsh option name like Mesh.MeshSizeFactor",
+            name
+        ));
+    }
+    if !unchecked {
+        return Err(format!(
+            "{} is not known to termsh, set Unchecked to y to pass it to Gmsh anyway",
+            name
+        ));
+    }
+    //the value is pasted into the script, it must not end the statement or comment out the rest
+    if value.is_empty()
+        || value.contains([';', '\n', '\r'])
+        || value.contains("//")
+        || value.contains("/*")
+    {
+        return Err(format!(
+            "{}: \"{}\" is not a single value",
+            name,
+            value.escape_debug()
+        ));
+    }
+    Ok(Some(format!(
+        "{} is not known to termsh, its value is not checked",
+        name
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_options_are_checked() {
+        assert_eq!(
+            validate_advanced("Mesh.MeshSizeFactor", "0.5", false),
+            Ok(None)
+        );
+        assert_eq!(
+            validate_advanced("General.Verbosity", "99", false),
+            Ok(None)
+        );
+        assert_eq!(
+            validate_advanced("General.Verbosity", "2", false),
+            Err(String::from(
+                "Verbosity: \"2\" is not valid, expected one of 4, 5, 99"
+            ))
+        );
+        //the catalog is checked even when the option is marked unchecked
+        assert!(validate_advanced("Mesh.Renumber", "2", true).is_err());
+    }
+
+    #[test]
+    fn table_and_scaling_options_are_refused() {
+        for name in [
+            "Mesh.MeshSizeMax",
+            "Geometry.OCCTargetUnit",
+            "Geometry.OCCScaling",
+            "Geometry.ScalingFactor",
+            "Mesh.ScalingFactor",
+        ] {
+            assert!(validate_advanced(name, "1", true).is_err(), "{}", name);
+        }
+        assert!(
+            SCALING_OPTIONS
+                .iter()
+                .all(|name| find_option(name).is_none())
+        );
+    }
+
+    #[test]
+    fn unknown_options_need_the_override() {
+        assert_eq!(
+            validate_advanced("Mesh.CgnsExportCpex0045", "1", false),
+            Err(String::from(
+                "Mesh.CgnsExportCpex0045 is not known to termsh, set Unchecked to y to pass it to Gmsh anyway"
+            ))
+        );
+        assert!(
+            validate_advanced("Mesh.CgnsExportCpex0045", "1", true)
+                .unwrap()
+                .is_some()
+        );
+        assert!(validate_advanced("Mesh.CgnsExportCpex0045", "1; Mesh 3", true).is_err());
+        for value in ["1\nMesh 3", "1\r", "1 // Mesh.Algorithm=6", "1 /* x */"] {
+            assert!(
+                validate_advanced("Mesh.CgnsExportCpex0045", value, true).is_err(),
+                "{:?}",
+                value
+            );
+        }
+        assert_eq!(
+            validate_advanced("Mesh.CgnsExportCpex0045", "1\nMesh 3", true),
+            Err(String::from(
+                "Mesh.CgnsExportCpex0045: \"1\\nMesh 3\" is not a single value"
+            ))
+        );
+        assert!(validate_advanced("Mesh.CgnsExportCpex0045", "1 / 2", true).is_ok());
+        assert!(validate_advanced("Mesh.CgnsExportCpex0045", "", true).is_err());
+        assert!(validate_advanced("Mesh", "1", true).is_err());
+        assert!(validate_advanced("Mesh.Size(1)", "1", true).is_err());
+    }
+
+    fn mesh_option(name: &str) -> &'static GmshOption {
+        MESH_OPTIONS
+            .iter()
+            .find(|option| option.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn mesh_options_are_checked_by_kind() {
+        let size = mesh_option("Mesh.MeshSizeMax");
+        assert!(size.validate("2.5").is_ok());
+        assert!(size.validate("1e-3").is_ok());
+        assert_eq!(
+            size.validate("0"),
+            Err(String::from(
+                "MeshSizeMax: \"0\" is not valid, expected a positive number"
+            ))
+        );
+
+        //integers within their range, no reals
+        let smoothing = mesh_option("Mesh.Smoothing");
+        assert!(smoothing.validate("0").is_ok());
+        assert!(smoothing.validate("100").is_ok());
+        assert!(smoothing.validate("101").is_err());
+        assert!(smoothing.validate("-1").is_err());
+        assert_eq!(
+            smoothing.validate("2.5"),
+            Err(String::from(
+                "Smoothing: \"2.5\" is not valid, expected an integer from 0 to 100"
+            ))
+        );
+
+        let order = mesh_option("Mesh.ElementOrder");
+        assert!(order.validate("2").is_ok());
+        assert_eq!(
+            order.validate("4"),
+            Err(String::from(
+                "ElementOrder: \"4\" is not valid, expected one of 1, 2, 3"
+            ))
+        );
+        let optimize = mesh_option("Mesh.Optimize");
+        assert!(optimize.validate("1").is_ok());
+        assert!(optimize.validate("yes").is_err());
+
+        //the defaults shown in gray are valid values
+        for option in MESH_OPTIONS.iter().filter(|option| option.default != "0") {
+            assert!(option.validate(option.default).is_ok(), "{}", option.name);
+        }
+    }
+
+    #[test]
+    fn mesh_options_are_written_when_set() {
+        use crate::gmsh_ctl::{GmshPara, ScriptAction};
+
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para
+            .mesh_paras
+            .set("Mesh.MeshSizeMax", String::from("5"));
+        gmsh_para
+            .mesh_paras
+            .set("Mesh.ElementOrder", String::from("2"));
+        gmsh_para.mesh_paras.set("Mesh.Smoothing", String::new());
+
+        let script = gmsh_para.build_script(ScriptAction::Preview).render();
+        let mesh_lines: Vec<&str> = script
+            .lines()
+            .filter(|line| {
+                MESH_OPTIONS
+                    .iter()
+                    .any(|option| line.starts_with(option.name))
+            })
+            .collect();
+        //in table order, the empty ones keep the Gmsh default
+        assert_eq!(mesh_lines, ["Mesh.MeshSizeMax=5;", "Mesh.ElementOrder=2;"]);
+    }
+}
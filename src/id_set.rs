@@ -0,0 +1,221 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+///A set of Gmsh entity tags
+///Parsed from input like "1,2,5", "3:10", "1-4" or "1 2 3", always kept sorted without duplicates
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IdSet {
+    ids: BTreeSet<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIdSetError(String);
+
+impl fmt::Display for ParseIdSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseIdSetError {}
+
+impl IdSet {
+    pub fn new() -> Self {
+        IdSet {
+            ids: BTreeSet::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.ids.iter().copied()
+    }
+
+    pub fn insert(&mut self, id: u32) {
+        self.ids.insert(id);
+    }
+
+    //consecutive ids grouped to (first, last)
+    fn ranges(&self) -> Vec<(u32, u32)> {
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for id in self.iter() {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == id => *last = id,
+                _ => ranges.push((id, id)),
+            }
+        }
+        ranges
+    }
+}
+
+impl FromIterator<u32> for IdSet {
+    fn from_iter<T: IntoIterator<Item = u32>>(iter: T) -> Self {
+        IdSet {
+            ids: iter.into_iter().collect(),
+        }
+    }
+}
+
+///largest span of a range, far above the entity count of any model,
+///it keeps a typo like "1-4000000000" from filling the memory
+pub const MAX_RANGE_SPAN: u32 = 100_000;
+
+fn parse_tag(text: &str) -> Result<u32, ParseIdSetError> {
+    match text.parse::<u32>() {
+        Ok(0) => Err(ParseIdSetError(String::from("entity tag 0 does not exist"))),
+        Ok(tag) => Ok(tag),
+        Err(_) => Err(ParseIdSetError(format!(
+            "\"{}\" is not an entity tag",
+            text
+        ))),
+    }
+}
+
+impl FromStr for IdSet {
+    type Err = ParseIdSetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //drop the whitespace around range separators, so "3 : 10" is one token
+        let mut compact = String::new();
+        for ch in s.trim().chars() {
+            if ch.is_whitespace() && compact.ends_with([':', '-']) {
+                continue;
+            }
+            if ch == ':' || ch == '-' {
+                compact = compact.trim_end().to_string();
+            }
+            compact.push(ch);
+        }
+
+        let mut ids = BTreeSet::new();
+        for token in compact
+            .split(|ch: char| ch == ',' || ch.is_whitespace())
+            .filter(|token| !token.is_empty())
+        {
+            match token.split_once([':', '-']) {
+                Some((first, last)) => {
+                    let (first, last) = (parse_tag(first)?, parse_tag(last)?);
+                    if first > last {
+                        return Err(ParseIdSetError(format!("range \"{}\" is reversed", token)));
+                    }
+                    if last - first >= MAX_RANGE_SPAN {
+                        return Err(ParseIdSetError(format!(
+                            "range \"{}\" has more than {} tags",
+                            token, MAX_RANGE_SPAN
+                        )));
+                    }
+                    ids.extend(first..=last);
+                }
+                None => {
+                    ids.insert(parse_tag(token)?);
+                }
+            }
+        }
+
+        Ok(IdSet { ids })
+    }
+}
+
+///Normalized form, also valid inside a Gmsh list, eg. "1:4, 7, 9"
+impl fmt::Display for IdSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .ranges()
+            .iter()
+            .map(|(first, last)| {
+                if first == last {
+                    first.to_string()
+                } else {
+                    format!("{}:{}", first, last)
+                }
+            })
+            .collect();
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl TryFrom<String> for IdSet {
+    type Error = ParseIdSetError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IdSet> for String {
+    fn from(value: IdSet) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<IdSet, ParseIdSetError> {
+        text.parse()
+    }
+
+    #[test]
+    fn separators_and_ranges() {
+        let ids = parse("5, 1-3 7:8  9").unwrap();
+        assert_eq!(ids.iter().collect::<Vec<_>>(), [1, 2, 3, 5, 7, 8, 9]);
+        assert_eq!(parse("3 : 5").unwrap().len(), 3);
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn format_round_trip() {
+        for text in ["1:4, 7, 9", "2", "10:12, 20:21", ""] {
+            let ids = parse(text).unwrap();
+            assert_eq!(ids.to_string(), text);
+            assert_eq!(parse(&ids.to_string()).unwrap(), ids);
+        }
+        //duplicates and order are normalized
+        assert_eq!(parse("4,3,2,1,3").unwrap().to_string(), "1:4");
+    }
+
+    #[test]
+    fn reversed_range_and_bad_tags() {
+        assert_eq!(
+            parse("5-2").unwrap_err().to_string(),
+            "range \"5-2\" is reversed"
+        );
+        assert!(parse("0").is_err());
+        assert!(parse("1,a").is_err());
+        assert!(parse("1-").is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(parse("4294967296").is_err());
+        assert!(parse("1-4000000000").is_err());
+        assert!(parse(&format!("1-{}", MAX_RANGE_SPAN + 1)).is_err());
+        assert_eq!(
+            parse(&format!("1-{}", MAX_RANGE_SPAN)).unwrap().len(),
+            MAX_RANGE_SPAN as usize
+        );
+        assert!(parse("4294967295").unwrap().contains(u32::MAX));
+    }
+
+    #[test]
+    fn serde_uses_the_text_form() {
+        let ids: IdSet = serde_json::from_str("\"1:3, 6\"").unwrap();
+        assert_eq!(serde_json::to_string(&ids).unwrap(), "\"1:3, 6\"");
+        assert!(serde_json::from_str::<IdSet>("\"1-4000000000\"").is_err());
+    }
+}
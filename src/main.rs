@@ -1,38 +1,242 @@
-use std::{path::Path, process};
+use std::process;
 
+use crate::coverage::check_coverage;
+use crate::error::{Result, TermshError};
+use crate::geometry::{GeometryPart, validate_parts};
+use crate::gmsh_ctl::{ExportFormat, ModelDimension, ScriptAction};
+use crate::gmsh_log::LogLevel;
+use crate::material::{MaterialLibrary, material_file};
+use crate::project::{DEFAULT_PROJECT_FILE, load_project, load_project_or_default};
 use crate::tui::termsh_run;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
+mod boundary_condition;
+mod boundary_layer;
+mod coverage;
+mod entity;
+mod error;
+mod field;
+mod geometry;
 mod gmsh_ctl;
+mod gmsh_log;
+mod gmsh_option;
+mod id_set;
+mod material;
+mod nastran;
+mod project;
+mod quantity;
 mod tui;
 
 fn main() {
-    //use clap to read in the step file name
+    //use clap to read in the geometry file names
     let args = CliArgs::parse();
 
-    //validate filename
-    //Check whether .step file name is legal and exist
-    if !args.step_file.contains(".step") {
-        println!("please input a .step file");
-        process::exit(1);
+    let result = match args.command {
+        Some(Command::Export {
+            project,
+            format,
+            print_script,
+            skip_coverage_check,
+        }) => export(&project, &format, print_script, skip_coverage_check),
+        None => {
+            let project_file = args
+                .project
+                .unwrap_or_else(|| DEFAULT_PROJECT_FILE.to_string());
+            run_tui(project_file, args.geometry_files)
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+//mesh and export in batch mode, everything comes from the project file
+fn export(
+    project: &str,
+    format: &[ExportFormat],
+    print_script: bool,
+    skip_coverage_check: bool,
+) -> Result<()> {
+    let mut gmsh_para = load_project(project)?;
+    validate_parts(&gmsh_para.geometry_parts)?;
+    let material_library = MaterialLibrary::load(&material_file(project))?;
+    gmsh_para.check_materials(&material_library)?;
+
+    //formats given on command line override the ones enabled in project
+    if !format.is_empty() {
+        gmsh_para.select_export_formats(format);
     }
 
-    if !Path::new(&args.step_file).exists() {
-        println!("file {} does not exist", args.step_file);
-        process::exit(1);
+    //an automatic dimension comes from the entities, which are queried once for the coverage too
+    //the printed script needs it as well, it would mesh in 3D otherwise
+    let mut entity_list = None;
+    if gmsh_para.model_dimension == ModelDimension::Auto || (!print_script && !skip_coverage_check)
+    {
+        let queried = gmsh_para.query_entities()?;
+        gmsh_para.detected_dimension = Some(queried.dimension());
+        entity_list = Some(queried);
     }
+    gmsh_para.check_boundary_layer()?;
+    gmsh_para.check_conditions()?; //curve loads depend on the dimension
 
-    if let Err(e) = termsh_run(args.step_file) {
-        panic!("{}", e)
+    if print_script {
+        print!("{}", gmsh_para.build_script(ScriptAction::Export).render());
+        return Ok(());
     }
 
-    //next: save a termsh_cache.json to store Physical Name and Physical IDs in current folder
-    //and when start, load these Physical Names and Physical IDs
+    //entities in no group or in two groups would silently be lost or doubled in the mesh
+    if !skip_coverage_check && let Some(entity_list) = &entity_list {
+        let report = check_coverage(&gmsh_para, entity_list);
+        if !report.is_clean() {
+            for warning in report.warnings() {
+                println!("warning: {}", warning);
+            }
+            return Err(TermshError::Coverage(report.warnings().len()));
+        }
+    }
+    gmsh_para.check_fields(entity_list.as_ref())?;
+    gmsh_para.check_advanced_options()?;
+    gmsh_para.check_decks(&material_library)?;
+
+    let gmsh_log = gmsh_para.export_batch()?;
+    for line in gmsh_log.lines() {
+        if line.level == LogLevel::Warning {
+            println!("{}", line.text);
+        }
+    }
+    for warning in nastran::write_decks(&gmsh_para, &material_library)? {
+        println!("warning: {}", warning);
+    }
+    for setting in gmsh_para.enabled_exports() {
+        println!("exported {}", gmsh_para.output_file_name(setting.format));
+    }
+
+    Ok(())
+}
+
+fn run_tui(project_file: String, geometry_files: Vec<String>) -> Result<()> {
+    let mut gmsh_para = load_project_or_default(&project_file)?;
+
+    //the geometry files given on command line override the ones stored in project
+    //a file already in the project keeps its scaling and translation
+    if !geometry_files.is_empty() {
+        gmsh_para.geometry_parts = geometry_files
+            .into_iter()
+            .map(|file| {
+                gmsh_para
+                    .geometry_parts
+                    .iter()
+                    .find(|part| part.file == file)
+                    .cloned()
+                    .unwrap_or_else(|| GeometryPart::new(file))
+            })
+            .collect();
+    }
+    validate_parts(&gmsh_para.geometry_parts)?;
+    let material_library = MaterialLibrary::load(&material_file(&project_file))?;
+
+    termsh_run(gmsh_para, material_library, project_file)
 }
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 struct CliArgs {
-    #[arg(short, long)]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Geometry files (.step/.stp, .iges/.igs, .brep or .geo) fused into one model, can be omitted when the project already stores them
     #[arg(index = 1)]
-    step_file: String,
+    geometry_files: Vec<String>,
+
+    /// Project file to load from and save to
+    #[arg(short, long)]
+    project: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Mesh and export without opening the TUI or the Gmsh GUI
+    Export {
+        /// Project file saved by termsh
+        #[arg(index = 1)]
+        project: String,
+
+        /// Formats to export, can be repeated, default to the formats enabled in project
+        #[arg(short, long, value_enum)]
+        format: Vec<ExportFormat>,
+
+        /// Print the .geo script that would be sent to Gmsh instead of running it
+        #[arg(long)]
+        print_script: bool,
+
+        /// Export even when entities are in no physical group or in more than one
+        #[arg(long)]
+        skip_coverage_check: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gmsh_ctl::GmshPara;
+
+    #[test]
+    fn coverage_failures_exit_with_2() {
+        assert_eq!(TermshError::Coverage(3).exit_code(), 2);
+        for error in [
+            TermshError::Input(String::from("no export format is enabled")),
+            TermshError::Gmsh(String::from("meshing failed")),
+            TermshError::Project(String::from("unknown version")),
+        ] {
+            assert_eq!(error.exit_code(), 1, "{}", error);
+        }
+    }
+
+    #[test]
+    fn export_formats_from_the_command_line() {
+        let args = CliArgs::try_parse_from([
+            "termsh",
+            "export",
+            "bracket.json",
+            "--format",
+            "msh41",
+            "-f",
+            "nas-free",
+            "--skip-coverage-check",
+        ])
+        .unwrap();
+        let Some(Command::Export {
+            project,
+            format,
+            print_script,
+            skip_coverage_check,
+        }) = args.command
+        else {
+            panic!("expected the export command");
+        };
+        assert_eq!(project, "bracket.json");
+        assert_eq!(format, [ExportFormat::Msh41, ExportFormat::NasFree]);
+        assert!(!print_script);
+        assert!(skip_coverage_check);
+        assert!(CliArgs::try_parse_from(["termsh", "export", "a.json", "-f", "stl"]).is_err());
+
+        //the given formats replace the ones enabled in the project
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.select_export_formats(&format);
+        let enabled: Vec<ExportFormat> = gmsh_para
+            .enabled_exports()
+            .iter()
+            .map(|setting| setting.format)
+            .collect();
+        assert_eq!(enabled, [ExportFormat::NasFree, ExportFormat::Msh41]);
+    }
+
+    #[test]
+    fn missing_project_is_not_exported() {
+        let project = std::env::temp_dir().join(format!("termsh_missing_{}.json", process::id()));
+        let error = export(project.to_str().unwrap(), &[], false, false).unwrap_err();
+        assert_eq!(error.exit_code(), 1);
+    }
 }
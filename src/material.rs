@@ -0,0 +1,258 @@
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TermshError};
+
+///user materials, kept next to the project file
+pub const MATERIAL_FILE: &str = "termsh_materials.json";
+
+///Isotropic material, in SI units (Pa, kg/m³, W/(m·K), 1/K, J/(kg·K), K)
+///the thermal properties are optional, only thermal analyses need them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Material {
+    pub name: String,
+    pub youngs_modulus: f64,
+    pub poisson_ratio: f64,
+    pub density: f64,
+    #[serde(default)]
+    pub thermal_conductivity: Option<f64>,
+    #[serde(default)]
+    pub thermal_expansion: Option<f64>,
+    #[serde(default)]
+    pub specific_heat: Option<f64>,
+    #[serde(default)]
+    pub reference_temperature: Option<f64>, //free of thermal strain, needed by temperature loads
+}
+
+//the built-in values are given at room temperature
+const ROOM_TEMPERATURE: f64 = 293.15;
+
+impl Material {
+    fn new(
+        name: &str,
+        [youngs_modulus, poisson_ratio, density]: [f64; 3],
+        [thermal_conductivity, thermal_expansion, specific_heat]: [f64; 3],
+    ) -> Self {
+        Material {
+            name: name.to_string(),
+            youngs_modulus,
+            poisson_ratio,
+            density,
+            thermal_conductivity: Some(thermal_conductivity),
+            thermal_expansion: Some(thermal_expansion),
+            specific_heat: Some(specific_heat),
+            reference_temperature: Some(ROOM_TEMPERATURE),
+        }
+    }
+
+    ///"E 210 GPa, nu 0.3, rho 7850 kg/m3", for the picker
+    pub fn summary(&self) -> String {
+        format!(
+            "E {} GPa, nu {}, rho {} kg/m3",
+            self.youngs_modulus / 1e9,
+            self.poisson_ratio,
+            self.density
+        )
+    }
+
+    fn check(&self) -> std::result::Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(String::from("a material has no name"));
+        }
+        if self.youngs_modulus <= 0.0 || self.density <= 0.0 {
+            return Err(format!(
+                "{}: youngs_modulus and density must be positive",
+                self.name
+            ));
+        }
+        if !(-1.0..0.5).contains(&self.poisson_ratio) {
+            return Err(format!(
+                "{}: poisson_ratio must be between -1 and 0.5",
+                self.name
+            ));
+        }
+        if self.reference_temperature.is_some_and(|t| t <= 0.0) {
+            return Err(format!(
+                "{}: reference_temperature is in K and must be positive",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+///Built-in materials, extended and overridden by name by the materials of MATERIAL_FILE
+#[derive(Debug, Clone)]
+pub struct MaterialLibrary {
+    pub materials: Vec<Material>,
+}
+
+impl MaterialLibrary {
+    pub fn builtin() -> Self {
+        MaterialLibrary {
+            materials: vec![
+                Material::new("Steel", [210e9, 0.3, 7850.0], [50.0, 12e-6, 460.0]),
+                Material::new(
+                    "Stainless Steel 304",
+                    [193e9, 0.29, 8000.0],
+                    [16.2, 17.3e-6, 500.0],
+                ),
+                Material::new(
+                    "Aluminium 6061-T6",
+                    [68.9e9, 0.33, 2700.0],
+                    [167.0, 23.6e-6, 896.0],
+                ),
+                Material::new(
+                    "Titanium Ti-6Al-4V",
+                    [113.8e9, 0.342, 4430.0],
+                    [6.7, 8.6e-6, 526.0],
+                ),
+                Material::new("Copper", [117e9, 0.34, 8960.0], [401.0, 16.5e-6, 385.0]),
+                Material::new(
+                    "Grey Cast Iron",
+                    [110e9, 0.26, 7200.0],
+                    [52.0, 10.8e-6, 460.0],
+                ),
+            ],
+        }
+    }
+
+    ///the built-in materials and those of the file, which is optional
+    pub fn load(filename: &Path) -> Result<Self> {
+        let mut library = Self::builtin();
+        if !filename.exists() {
+            return Ok(library);
+        }
+
+        let content = read_to_string(filename).map_err(TermshError::io(format!(
+            "failed to read materials {}",
+            filename.display()
+        )))?;
+        let invalid = |reason: String| {
+            TermshError::Input(format!(
+                "materials {} are invalid: {}",
+                filename.display(),
+                reason
+            ))
+        };
+
+        let materials: Vec<Material> =
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        for material in materials {
+            material.check().map_err(invalid)?;
+            library
+                .materials
+                .retain(|other| other.name != material.name);
+            library.materials.push(material);
+        }
+
+        Ok(library)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Material> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+}
+
+///MATERIAL_FILE in the folder of the project file
+pub fn material_file(project_file: &str) -> PathBuf {
+    Path::new(project_file).with_file_name(MATERIAL_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a project folder of its own for each test
+    fn project_folder(test: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("termsh_{}_{}", test, std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn builtin_materials_are_valid() {
+        let library = MaterialLibrary::builtin();
+        assert_eq!(library.materials.len(), 6);
+        for material in &library.materials {
+            assert!(material.check().is_ok(), "{}", material.name);
+            assert_eq!(material.reference_temperature, Some(ROOM_TEMPERATURE));
+        }
+        assert_eq!(
+            library.get("Steel").unwrap().summary(),
+            "E 210 GPa, nu 0.3, rho 7850 kg/m3"
+        );
+        assert!(library.get("steel").is_none());
+    }
+
+    #[test]
+    fn file_next_to_the_project() {
+        assert_eq!(
+            material_file("work/bracket.json"),
+            Path::new("work").join(MATERIAL_FILE)
+        );
+        assert_eq!(material_file("bracket.json"), Path::new(MATERIAL_FILE));
+    }
+
+    #[test]
+    fn file_adds_and_overrides_materials() {
+        let folder = project_folder("materials_added");
+        let file = folder.join(MATERIAL_FILE);
+        //a missing file leaves the built-in materials
+        assert_eq!(MaterialLibrary::load(&file).unwrap().materials.len(), 6);
+
+        std::fs::write(
+            &file,
+            r#"[{"name": "PA66 GF30", "youngs_modulus": 9.5e9, "poisson_ratio": 0.35, "density": 1360},
+                {"name": "Steel", "youngs_modulus": 200e9, "poisson_ratio": 0.29, "density": 7800,
+                 "reference_temperature": 300}]"#,
+        )
+        .unwrap();
+        let library = MaterialLibrary::load(&file).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(library.materials.len(), 7);
+        let pa66 = library.get("PA66 GF30").unwrap();
+        assert_eq!(pa66.thermal_expansion, None);
+        assert_eq!(pa66.reference_temperature, None);
+        let steel = library.get("Steel").unwrap();
+        assert_eq!(steel.youngs_modulus, 200e9);
+        assert_eq!(steel.thermal_conductivity, None);
+        assert_eq!(steel.reference_temperature, Some(300.0));
+    }
+
+    #[test]
+    fn invalid_materials_are_refused() {
+        let folder = project_folder("materials_invalid");
+        let file = folder.join(MATERIAL_FILE);
+        let errors: Vec<String> = [
+            r#"[{"name": " ", "youngs_modulus": 1e9, "poisson_ratio": 0.3, "density": 1000}]"#,
+            r#"[{"name": "a", "youngs_modulus": 0, "poisson_ratio": 0.3, "density": 1000}]"#,
+            r#"[{"name": "b", "youngs_modulus": 1e9, "poisson_ratio": 0.5, "density": 1000}]"#,
+            r#"[{"name": "c", "youngs_modulus": 1e9, "poisson_ratio": 0.3, "density": 1000,
+                 "reference_temperature": -20}]"#,
+            r#"[{"name": "d", "youngs_modulus": 1e9}]"#,
+        ]
+        .into_iter()
+        .map(|content| {
+            std::fs::write(&file, content).unwrap();
+            MaterialLibrary::load(&file).unwrap_err().to_string()
+        })
+        .collect();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        for (error, expected) in errors.iter().zip([
+            "a material has no name",
+            "a: youngs_modulus and density must be positive",
+            "b: poisson_ratio must be between -1 and 0.5",
+            "c: reference_temperature is in K",
+            "missing field",
+        ]) {
+            assert!(error.contains(expected), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,1520 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use crate::boundary_condition::{BcKind, BoundaryCondition};
+use crate::error::{Result, TermshError};
+use crate::geometry::LengthUnit;
+use crate::gmsh_ctl::{ExportFormat, GmshPara};
+use crate::material::{Material, MaterialLibrary};
+use crate::quantity::Quantity;
+
+//set ids selected by the subcase
+const SPC_SET: u32 = 1;
+const LOAD_SET: u32 = 2;
+const TEMPERATURE_SET: u32 = 3;
+
+///Rewrite the exported Nastran files marked as decks into complete SOL 101 decks:
+///case control, properties, materials, constraints and loads around the Gmsh mesh
+///returns warnings about the boundary conditions which could not be written
+pub fn write_decks(gmsh_para: &GmshPara, library: &MaterialLibrary) -> Result<Vec<String>> {
+    gmsh_para.check_decks(library)?;
+    let points = read_points(&gmsh_para.point_output_file_name())?;
+
+    let mut warnings = Vec::new();
+    for setting in gmsh_para.enabled_exports() {
+        if !(setting.format.is_nastran() && setting.nastran_deck) {
+            continue;
+        }
+
+        let file_name = gmsh_para.output_file_name(setting.format);
+        let content = fs::read_to_string(&file_name)
+            .map_err(TermshError::io(format!("failed to read {}", file_name)))?;
+        let mesh = BulkMesh::parse(&content)
+            .map_err(|e| TermshError::Gmsh(format!("{} can not be read: {}", file_name, e)))?;
+
+        let title = Path::new(&file_name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut deck = Deck::new(FieldFormat::of(setting.format), gmsh_para.units.target);
+        deck.write(gmsh_para, library, &mesh, &points);
+
+        fs::write(&file_name, deck.render(&title))
+            .map_err(TermshError::io(format!("failed to write {}", file_name)))?;
+        warnings.extend(
+            deck.warnings
+                .iter()
+                .map(|warning| format!("{}: {}", file_name, warning)),
+        );
+    }
+    Ok(warnings)
+}
+
+//coordinates of the points carrying boundary conditions, printed by the export script
+fn read_points(file_name: &str) -> Result<HashMap<u32, [f64; 3]>> {
+    let mut points = HashMap::new();
+    if !Path::new(file_name).exists() {
+        return Ok(points);
+    }
+
+    let content = fs::read_to_string(file_name)
+        .map_err(TermshError::io(format!("failed to read {}", file_name)))?;
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let point = match fields.as_slice() {
+            ["POINT", tag, x, y, z] => tag.parse::<u32>().ok().zip(
+                [x, y, z]
+                    .map(|coord| coord.parse::<f64>().ok())
+                    .into_iter()
+                    .collect::<Option<Vec<f64>>>(),
+            ),
+            [] => continue,
+            _ => None,
+        };
+        let Some((tag, coords)) = point else {
+            return Err(TermshError::Gmsh(format!(
+                "unexpected point line in {}: {}",
+                file_name, line
+            )));
+        };
+        points.insert(tag, [coords[0], coords[1], coords[2]]);
+    }
+
+    fs::remove_file(file_name)
+        .map_err(TermshError::io(format!("failed to remove {}", file_name)))?;
+    Ok(points)
+}
+
+///Field format of the cards, the same as the one Gmsh wrote
+#[derive(Debug, Clone, Copy)]
+enum FieldFormat {
+    Small, //8 fields of 8 characters per line
+    Large, //4 fields of 16 characters per line, the name marked by *
+    Free,  //comma separated
+}
+
+impl FieldFormat {
+    fn of(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::NasLarge => FieldFormat::Large,
+            ExportFormat::NasFree => FieldFormat::Free,
+            _ => FieldFormat::Small,
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            FieldFormat::Small => 8,
+            FieldFormat::Large | FieldFormat::Free => 16,
+        }
+    }
+
+    ///the lines of a card, continuation lines start with + (or * in large field)
+    fn render(self, name: &str, fields: &[Field]) -> String {
+        let mut fields: Vec<String> = fields
+            .iter()
+            .map(|field| field.render(self.width()))
+            .collect();
+        while fields.last().is_some_and(|field| field.is_empty()) {
+            fields.pop();
+        }
+
+        let per_line = match self {
+            FieldFormat::Large => 4,
+            FieldFormat::Small | FieldFormat::Free => 8,
+        };
+        let mut lines = Vec::new();
+        for (idx, chunk) in fields.chunks(per_line).enumerate() {
+            let line = match self {
+                FieldFormat::Small => {
+                    let head = if idx == 0 { name } else { "+" };
+                    let mut line = format!("{:<8}", head);
+                    for field in chunk {
+                        line += &format!("{:<8}", field);
+                    }
+                    line
+                }
+                FieldFormat::Large => {
+                    let head = if idx == 0 {
+                        format!("{}*", name)
+                    } else {
+                        String::from("*")
+                    };
+                    let mut line = format!("{:<8}", head);
+                    for field in chunk {
+                        line += &format!("{:<16}", field);
+                    }
+                    line
+                }
+                FieldFormat::Free => {
+                    let head = if idx == 0 { name } else { "+" };
+                    format!("{},{}", head, chunk.join(","))
+                }
+            };
+            lines.push(line.trim_end().to_string());
+        }
+        if lines.is_empty() {
+            lines.push(name.to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Int(i64),
+    Real(f64),
+    Text(String),
+    Blank,
+}
+
+impl Field {
+    fn id(id: u32) -> Self {
+        Field::Int(id as i64)
+    }
+
+    fn render(&self, width: usize) -> String {
+        match self {
+            Field::Int(value) => value.to_string(),
+            Field::Real(value) => format_real(*value, width),
+            Field::Text(text) => text.clone(),
+            Field::Blank => String::new(),
+        }
+    }
+}
+
+//Nastran reals need a decimal point and must fit the field
+//small values, eg. densities in t/mm3, are easier to read with an exponent
+fn format_real(value: f64, width: usize) -> String {
+    let plain = value.to_string();
+    let plain = if plain.contains('.') {
+        plain
+    } else {
+        plain + "."
+    };
+    if plain.len() <= width && (value == 0.0 || value.abs() >= 1e-3) {
+        return plain;
+    }
+
+    let shortest = format!("{:E}", value);
+    if shortest.len() <= width && shortest.contains('.') {
+        return shortest;
+    }
+    for precision in (1..width).rev() {
+        let text = format!("{:.*E}", precision, value);
+        if text.len() <= width {
+            return text;
+        }
+    }
+    format!("{:.0E}", value).replace('E', ".E")
+}
+
+//Nastran reals may leave out the E of the exponent, eg. 1.5-3
+fn parse_real(field: &str) -> Option<f64> {
+    let field = field.trim().replace(['D', 'd'], "E");
+    if let Ok(value) = field.parse::<f64>() {
+        return Some(value);
+    }
+    let sign = field.get(1..)?.rfind(['+', '-'])? + 1;
+    format!("{}E{}", &field[..sign], &field[sign..])
+        .parse()
+        .ok()
+}
+
+struct Grid {
+    id: u32,
+    coords: [f64; 3],
+}
+
+struct Element {
+    kind: String, //card name, eg. CTETRA
+    id: u32,
+    pid: u32, //the physical id, with Mesh.SaveElementTagType=2
+    nodes: Vec<u32>,
+}
+
+impl Element {
+    fn dim(&self) -> Option<u8> {
+        element_dim(&self.kind)
+    }
+
+    //the vertices, midside nodes follow them
+    fn corners(&self) -> &[u32] {
+        let count = match self.kind.as_str() {
+            "CTETRA" => 4,
+            "CPYRAM" => 5,
+            "CPENTA" => 6,
+            "CHEXA" => 8,
+            "CTRIA3" | "CTRIA6" => 3,
+            "CQUAD4" | "CQUAD8" => 4,
+            _ => 2,
+        };
+        &self.nodes[..count.min(self.nodes.len())]
+    }
+
+    ///(corners, G1, G34) of the faces, as PLOAD4 locates a face of a solid:
+    ///G1 a corner of the face, G34 the corner opposite the face for tetrahedra,
+    ///the diagonal corner for quadrilateral faces and none for other triangles
+    fn solid_faces(&self) -> Vec<(Vec<u32>, u32, Option<u32>)> {
+        let faces: &[&[usize]] = match self.kind.as_str() {
+            "CTETRA" => &[&[0, 1, 2], &[0, 1, 3], &[1, 2, 3], &[0, 2, 3]],
+            "CPYRAM" => &[
+                &[0, 1, 2, 3],
+                &[0, 1, 4],
+                &[1, 2, 4],
+                &[2, 3, 4],
+                &[3, 0, 4],
+            ],
+            "CPENTA" => &[
+                &[0, 1, 2],
+                &[3, 4, 5],
+                &[0, 1, 4, 3],
+                &[1, 2, 5, 4],
+                &[2, 0, 3, 5],
+            ],
+            "CHEXA" => &[
+                &[0, 1, 2, 3],
+                &[4, 5, 6, 7],
+                &[0, 1, 5, 4],
+                &[1, 2, 6, 5],
+                &[2, 3, 7, 6],
+                &[3, 0, 4, 7],
+            ],
+            _ => &[],
+        };
+
+        let corners = self.corners();
+        if corners.len()
+            < faces
+                .iter()
+                .flat_map(|face| face.iter())
+                .max()
+                .map_or(0, |max| max + 1)
+        {
+            return Vec::new();
+        }
+        faces
+            .iter()
+            .map(|face| {
+                let face_nodes: Vec<u32> = face.iter().map(|&idx| corners[idx]).collect();
+                let g34 = match (self.kind.as_str(), face_nodes.len()) {
+                    ("CTETRA", _) => corners
+                        .iter()
+                        .copied()
+                        .find(|node| !face_nodes.contains(node)),
+                    (_, 4) => Some(face_nodes[2]),
+                    _ => None,
+                };
+                (face_nodes.clone(), face_nodes[0], g34)
+            })
+            .collect()
+    }
+}
+
+fn element_dim(kind: &str) -> Option<u8> {
+    match kind {
+        "CTETRA" | "CPYRAM" | "CPENTA" | "CHEXA" => Some(3),
+        "CTRIA3" | "CTRIA6" | "CQUAD4" | "CQUAD8" => Some(2),
+        "CBAR" | "CROD" | "CBEAM" => Some(1),
+        _ => None,
+    }
+}
+
+//shape functions [N, dN/dr, dN/ds] of the nodes of a boundary element at (r, s),
+//nodes in Nastran order: corners first, then the midside nodes
+fn shape_functions(kind: &str, node_count: usize, r: f64, s: f64) -> Option<Vec<[f64; 3]>> {
+    const QUAD_CORNERS: [(f64, f64); 4] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+
+    let functions = match (kind, node_count) {
+        //lines on -1..1
+        (_, 2) if element_dim(kind) == Some(1) => {
+            vec![[(1.0 - r) / 2.0, -0.5, 0.0], [(1.0 + r) / 2.0, 0.5, 0.0]]
+        }
+        (_, 3) if element_dim(kind) == Some(1) => vec![
+            [r * (r - 1.0) / 2.0, r - 0.5, 0.0],
+            [r * (r + 1.0) / 2.0, r + 0.5, 0.0],
+            [1.0 - r * r, -2.0 * r, 0.0],
+        ],
+        //triangles on (0, 0), (1, 0), (0, 1)
+        ("CTRIA3", 3) => vec![[1.0 - r - s, -1.0, -1.0], [r, 1.0, 0.0], [s, 0.0, 1.0]],
+        ("CTRIA6", 6) => {
+            let t = 1.0 - r - s;
+            vec![
+                [t * (2.0 * t - 1.0), 1.0 - 4.0 * t, 1.0 - 4.0 * t],
+                [r * (2.0 * r - 1.0), 4.0 * r - 1.0, 0.0],
+                [s * (2.0 * s - 1.0), 0.0, 4.0 * s - 1.0],
+                [4.0 * t * r, 4.0 * (t - r), -4.0 * r],
+                [4.0 * r * s, 4.0 * s, 4.0 * r],
+                [4.0 * s * t, -4.0 * s, 4.0 * (t - s)],
+            ]
+        }
+        //quadrilaterals on -1..1 in both directions
+        ("CQUAD4", 4) => QUAD_CORNERS
+            .iter()
+            .map(|&(ri, si)| {
+                [
+                    (1.0 + ri * r) * (1.0 + si * s) / 4.0,
+                    ri * (1.0 + si * s) / 4.0,
+                    si * (1.0 + ri * r) / 4.0,
+                ]
+            })
+            .collect(),
+        ("CQUAD8", 8) => {
+            let mut functions: Vec<[f64; 3]> = QUAD_CORNERS
+                .iter()
+                .map(|&(ri, si)| {
+                    let (a, b) = (ri * r, si * s);
+                    [
+                        (1.0 + a) * (1.0 + b) * (a + b - 1.0) / 4.0,
+                        ri * (1.0 + b) * (2.0 * a + b) / 4.0,
+                        si * (1.0 + a) * (a + 2.0 * b) / 4.0,
+                    ]
+                })
+                .collect();
+            //midside nodes of the edges 1-2, 2-3, 3-4 and 4-1
+            for (ri, si) in [(0.0, -1.0), (1.0, 0.0), (0.0, 1.0), (-1.0, 0.0)] {
+                functions.push(if ri == 0.0 {
+                    [
+                        (1.0 - r * r) * (1.0 + si * s) / 2.0,
+                        -r * (1.0 + si * s),
+                        si * (1.0 - r * r) / 2.0,
+                    ]
+                } else {
+                    [
+                        (1.0 + ri * r) * (1.0 - s * s) / 2.0,
+                        ri * (1.0 - s * s) / 2.0,
+                        -s * (1.0 + ri * r),
+                    ]
+                });
+            }
+            functions
+        }
+        _ => return None,
+    };
+    Some(functions)
+}
+
+//integration points (r, s, weight) of the reference element, exact for straight edges
+fn integration_points(kind: &str) -> Vec<(f64, f64, f64)> {
+    let gauss = 1.0 / 3f64.sqrt();
+    match element_dim(kind) {
+        Some(1) => vec![(-gauss, 0.0, 1.0), (gauss, 0.0, 1.0)],
+        _ if kind.starts_with("CTRIA") => vec![
+            (1.0 / 6.0, 1.0 / 6.0, 1.0 / 6.0),
+            (2.0 / 3.0, 1.0 / 6.0, 1.0 / 6.0),
+            (1.0 / 6.0, 2.0 / 3.0, 1.0 / 6.0),
+        ],
+        _ => [
+            (-gauss, -gauss),
+            (gauss, -gauss),
+            (gauss, gauss),
+            (-gauss, gauss),
+        ]
+        .map(|(r, s)| (r, s, 1.0))
+        .to_vec(),
+    }
+}
+
+///GRIDs and elements of a Nastran file written by Gmsh, other cards are dropped
+#[derive(Default)]
+struct BulkMesh {
+    grids: Vec<Grid>,
+    elements: Vec<Element>,
+}
+
+impl BulkMesh {
+    fn parse(content: &str) -> std::result::Result<Self, String> {
+        let mut mesh = BulkMesh::default();
+        for card in read_cards(content) {
+            let int = |idx: usize| card.get(idx).and_then(|field| field.parse::<u32>().ok());
+            let name = card[0].as_str();
+
+            if name == "GRID" {
+                let id = int(1).ok_or_else(|| format!("GRID without id: {}", card.join(",")))?;
+                let mut coords = [0.0; 3];
+                for (axis, coord) in coords.iter_mut().enumerate() {
+                    let field = card.get(3 + axis).map_or("", |field| field.as_str());
+                    if !field.is_empty() {
+                        *coord = parse_real(field)
+                            .ok_or_else(|| format!("GRID {}: \"{}\" is not a number", id, field))?;
+                    }
+                }
+                mesh.grids.push(Grid { id, coords });
+            } else if element_dim(name).is_some() {
+                let (Some(id), Some(pid)) = (int(1), int(2)) else {
+                    return Err(format!(
+                        "{} without id or property: {}",
+                        name,
+                        card.join(",")
+                    ));
+                };
+                //extra fields after the nodes, eg. the orientation of a CBAR, are not node ids
+                let nodes = card[3..]
+                    .iter()
+                    .map_while(|field| field.parse::<u32>().ok())
+                    .collect();
+                mesh.elements.push(Element {
+                    kind: name.to_string(),
+                    id,
+                    pid,
+                    nodes,
+                });
+            }
+        }
+
+        if mesh.grids.is_empty() {
+            return Err(String::from("it has no GRID"));
+        }
+        Ok(mesh)
+    }
+
+    ///elements of a physical group, found by their dimension and physical id
+    fn group_elements(&self, dim: u8, pid: u32) -> Vec<&Element> {
+        self.elements
+            .iter()
+            .filter(|element| element.dim() == Some(dim) && element.pid == pid)
+            .collect()
+    }
+
+    ///Nodes of boundary elements with their share of a total force spread evenly over the
+    ///area or length, from the integrals of the shape functions, so midside nodes of second
+    ///order elements get their consistent part and small elements less than large ones
+    fn load_shares(&self, elements: &[&Element]) -> BTreeMap<u32, f64> {
+        let coords: HashMap<u32, [f64; 3]> = self
+            .grids
+            .iter()
+            .map(|grid| (grid.id, grid.coords))
+            .collect();
+        let mut shares: BTreeMap<u32, f64> = BTreeMap::new();
+
+        for element in elements {
+            let nodes: Option<Vec<[f64; 3]>> = element
+                .nodes
+                .iter()
+                .map(|node| coords.get(node).copied())
+                .collect();
+            let Some(nodes) = nodes else {
+                continue;
+            };
+            //listed even without a share, the constraints of the group are on every node
+            for node in &element.nodes {
+                shares.entry(*node).or_default();
+            }
+            for (r, s, weight) in integration_points(&element.kind) {
+                let Some(functions) = shape_functions(&element.kind, nodes.len(), r, s) else {
+                    break;
+                };
+                let mut dr = [0.0; 3];
+                let mut ds = [0.0; 3];
+                for (function, node) in functions.iter().zip(&nodes) {
+                    for axis in 0..3 {
+                        dr[axis] += function[1] * node[axis];
+                        ds[axis] += function[2] * node[axis];
+                    }
+                }
+                //length of the tangent for lines, area of the parallelogram for surfaces
+                let jacobian = if element.dim() == Some(1) {
+                    dr.iter().map(|d| d * d).sum::<f64>().sqrt()
+                } else {
+                    let normal = [
+                        dr[1] * ds[2] - dr[2] * ds[1],
+                        dr[2] * ds[0] - dr[0] * ds[2],
+                        dr[0] * ds[1] - dr[1] * ds[0],
+                    ];
+                    normal.iter().map(|n| n * n).sum::<f64>().sqrt()
+                };
+                for (function, node) in functions.iter().zip(&element.nodes) {
+                    *shares.entry(*node).or_default() += function[0] * jacobian * weight;
+                }
+            }
+        }
+
+        let total: f64 = shares.values().sum();
+        let count = shares.len() as f64;
+        for share in shares.values_mut() {
+            //degenerate elements, shared equally as for points
+            *share = if total > 0.0 {
+                *share / total
+            } else {
+                1.0 / count
+            };
+        }
+        shares
+    }
+
+    ///GRID on a geometry point, Gmsh puts a node on every point of the meshed curves
+    ///the coordinates are printed with 12 digits, so a small fraction of the model size is allowed
+    fn grid_at(&self, coords: [f64; 3]) -> Option<u32> {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for grid in &self.grids {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(grid.coords[axis]);
+                max[axis] = max[axis].max(grid.coords[axis]);
+            }
+        }
+        let size = (0..3)
+            .map(|axis| (max[axis] - min[axis]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        let tolerance = (size * 1e-9).max(1e-12);
+
+        let distance = |grid: &Grid| {
+            (0..3)
+                .map(|axis| (grid.coords[axis] - coords[axis]).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        };
+        self.grids
+            .iter()
+            .map(|grid| (distance(grid), grid.id))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .filter(|(distance, _)| *distance <= tolerance)
+            .map(|(_, id)| id)
+    }
+}
+
+//fields of the bulk data cards, continuations joined and their markers dropped
+fn read_cards(content: &str) -> Vec<Vec<String>> {
+    let begins_bulk = |line: &str| line.trim_start().to_uppercase().starts_with("BEGIN BULK");
+    let mut in_bulk = !content.lines().any(begins_bulk);
+
+    let mut cards: Vec<Vec<String>> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.starts_with('$') {
+            continue;
+        }
+        if begins_bulk(line) {
+            in_bulk = true;
+            continue;
+        }
+        if line.trim_start().to_uppercase().starts_with("ENDDATA") {
+            break;
+        }
+        if !in_bulk {
+            continue;
+        }
+
+        let mut fields = split_fields(line);
+        if line.starts_with(['+', '*', ' ', ',']) {
+            if let Some(card) = cards.last_mut() {
+                card.extend(fields.drain(1..));
+            }
+        } else {
+            fields[0] = fields[0].trim_end_matches('*').to_uppercase();
+            cards.push(fields);
+        }
+    }
+    cards
+}
+
+//the name (or continuation marker) and the data fields of a line, without field 10
+fn split_fields(line: &str) -> Vec<String> {
+    if line.contains(',') {
+        let mut fields: Vec<String> = line
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .collect();
+        fields.truncate(9);
+        return fields;
+    }
+
+    let chars: Vec<char> = line.chars().collect();
+    let field = |start: usize, width: usize| -> String {
+        chars
+            .iter()
+            .skip(start)
+            .take(width)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    };
+    let head = field(0, 8);
+    let (width, count) = if head.starts_with('*') || head.ends_with('*') {
+        (16, 4)
+    } else {
+        (8, 8)
+    };
+
+    let mut fields = vec![head];
+    fields.extend((0..count).map(|idx| field(8 + idx * width, width)));
+    fields
+}
+
+///Bulk data of the deck, in the order it is written
+struct Deck {
+    format: FieldFormat,
+    length: LengthUnit,
+    lines: Vec<String>,
+    used_sets: BTreeSet<u32>,
+    warnings: Vec<String>,
+    reference_temperature: Option<f64>, //TEMPD of the nodes without a Temperature condition
+    rotations: bool,                    //shell grids have rotational DOFs, solid grids do not
+}
+
+impl Deck {
+    fn new(format: FieldFormat, length: LengthUnit) -> Self {
+        Deck {
+            format,
+            length,
+            lines: Vec::new(),
+            used_sets: BTreeSet::new(),
+            warnings: Vec::new(),
+            reference_temperature: None,
+            rotations: false,
+        }
+    }
+
+    fn card(&mut self, name: &str, fields: Vec<Field>) {
+        self.lines.push(self.format.render(name, &fields));
+    }
+
+    fn comment(&mut self, text: &str) {
+        self.lines.push(format!("$ {}", text));
+    }
+
+    //skipped conditions are noted in the deck too
+    fn warn(&mut self, warning: String) {
+        self.comment(&warning);
+        self.warnings.push(warning);
+    }
+
+    fn write(
+        &mut self,
+        gmsh_para: &GmshPara,
+        library: &MaterialLibrary,
+        mesh: &BulkMesh,
+        points: &HashMap<u32, [f64; 3]>,
+    ) {
+        let dim = gmsh_para.mesh_dimension();
+        self.rotations = dim == 2;
+        self.card(
+            "PARAM",
+            vec![Field::Text(String::from("POST")), Field::Int(-1)],
+        );
+
+        self.comment("nodes");
+        for grid in &mesh.grids {
+            let mut fields = vec![Field::id(grid.id), Field::Blank];
+            fields.extend(grid.coords.iter().map(|&coord| Field::Real(coord)));
+            self.card("GRID", fields);
+        }
+
+        //the elements of the boundaries only locate the boundary conditions
+        self.comment(if dim == 3 {
+            "solid elements"
+        } else {
+            "shell elements"
+        });
+        let structural: Vec<&Element> = mesh
+            .elements
+            .iter()
+            .filter(|element| element.dim() == Some(dim))
+            .collect();
+        for element in &structural {
+            let mut fields = vec![Field::id(element.id), Field::id(element.pid)];
+            fields.extend(element.nodes.iter().map(|&node| Field::id(node)));
+            self.card(&element.kind, fields);
+        }
+
+        self.write_properties(gmsh_para, library);
+
+        self.comment("boundary conditions");
+        let faces: HashMap<Vec<u32>, (u32, u32, Option<u32>)> = structural
+            .iter()
+            .flat_map(|element| {
+                element
+                    .solid_faces()
+                    .into_iter()
+                    .map(|(mut corners, g1, g34)| {
+                        corners.sort_unstable();
+                        (corners, (element.id, g1, g34))
+                    })
+            })
+            .collect();
+        let groups = gmsh_para
+            .surf_phy_list
+            .iter()
+            .map(|phys| {
+                (
+                    "Surface",
+                    2,
+                    &phys.name,
+                    &phys.phys_id,
+                    &phys.boundary_conditions,
+                )
+            })
+            .chain(gmsh_para.curve_phy_list.iter().map(|phys| {
+                (
+                    "Curve",
+                    1,
+                    &phys.name,
+                    &phys.phys_id,
+                    &phys.boundary_conditions,
+                )
+            }));
+        for (kind, group_dim, name, phys_id, conditions) in groups {
+            if conditions.is_empty() {
+                continue;
+            }
+            let group = format!("{} {}", kind, name);
+            let elements = match phys_id.parse::<u32>() {
+                Ok(pid) => mesh.group_elements(group_dim, pid),
+                Err(_) => Vec::new(),
+            };
+            if elements.is_empty() {
+                self.warn(format!(
+                    "{} has no elements in the mesh, its boundary conditions are not written",
+                    group
+                ));
+                continue;
+            }
+
+            let nodes = mesh.load_shares(&elements);
+            for condition in conditions {
+                self.write_condition(
+                    condition,
+                    &group,
+                    group_dim == 2 && dim == 3,
+                    &elements,
+                    &nodes,
+                    &faces,
+                );
+            }
+        }
+
+        //Gmsh writes no elements for points, their nodes are found by the coordinates
+        for phys in &gmsh_para.point_phy_list {
+            if phys.boundary_conditions.is_empty() {
+                continue;
+            }
+            let group = format!("Point {}", phys.name);
+            let mut nodes = BTreeMap::new();
+            for point_id in phys.point_ids.iter() {
+                match points
+                    .get(&point_id)
+                    .and_then(|&coords| mesh.grid_at(coords))
+                {
+                    Some(node) => {
+                        nodes.insert(node, 0.0);
+                    }
+                    None => self.warn(format!(
+                        "{}: point {} has no node in the mesh",
+                        group, point_id
+                    )),
+                }
+            }
+            if nodes.is_empty() {
+                self.warn(format!(
+                    "{} has no nodes in the mesh, its boundary conditions are not written",
+                    group
+                ));
+                continue;
+            }
+            let share = 1.0 / nodes.len() as f64;
+            nodes
+                .values_mut()
+                .for_each(|node_share| *node_share = share);
+
+            for condition in &phys.boundary_conditions {
+                self.write_condition(condition, &group, false, &[], &nodes, &faces);
+            }
+        }
+
+        //nodes without a Temperature condition stay free of thermal strain
+        if self.used_sets.contains(&TEMPERATURE_SET)
+            && let Some(reference_temperature) = self.reference_temperature
+        {
+            self.comment("temperature of the other nodes");
+            self.card(
+                "TEMPD",
+                vec![
+                    Field::id(TEMPERATURE_SET),
+                    Field::Real(reference_temperature),
+                ],
+            );
+        }
+
+        if !self.used_sets.contains(&SPC_SET) {
+            self.warnings.push(String::from(
+                "no Fixed condition, the model is not supported for a static analysis",
+            ));
+        }
+    }
+
+    //PSOLID per volume group of a solid model, PSHELL per surface group of a shell model
+    fn write_properties(&mut self, gmsh_para: &GmshPara, library: &MaterialLibrary) {
+        let groups: Vec<(&String, &String, &String, Option<&String>)> =
+            if gmsh_para.mesh_dimension() == 3 {
+                self.comment("properties of the Physical Volumes");
+                gmsh_para
+                    .vol_phy_list
+                    .iter()
+                    .map(|phys| (&phys.name, &phys.phys_id, &phys.material, None))
+                    .collect()
+            } else {
+                self.comment("properties of the Physical Surfaces");
+                gmsh_para
+                    .surf_phy_list
+                    .iter()
+                    .map(|phys| {
+                        (
+                            &phys.name,
+                            &phys.phys_id,
+                            &phys.material,
+                            Some(&phys.thickness),
+                        )
+                    })
+                    .collect()
+            };
+
+        let mut materials: Vec<&Material> = Vec::new();
+        for (name, phys_id, material_name, thickness) in groups {
+            //refused by check_decks, kept out of the deck to be safe
+            let (Ok(pid), Some(material)) = (phys_id.parse::<u32>(), library.get(material_name))
+            else {
+                continue;
+            };
+            let mid = match materials.iter().position(|used| used.name == material.name) {
+                Some(idx) => idx + 1,
+                None => {
+                    materials.push(material);
+                    materials.len()
+                }
+            } as u32;
+
+            self.comment(&format!("{}: {}", name, material.name));
+            match thickness.and_then(|thickness| thickness.parse::<f64>().ok()) {
+                Some(thickness) => self.card(
+                    "PSHELL",
+                    vec![
+                        Field::id(pid),
+                        Field::id(mid),
+                        Field::Real(thickness),
+                        Field::id(mid),
+                    ],
+                ),
+                None => self.card("PSOLID", vec![Field::id(pid), Field::id(mid)]),
+            }
+        }
+
+        self.comment("materials");
+        for (idx, material) in materials.iter().enumerate() {
+            self.comment(&material.name);
+            let fields = vec![
+                Field::id(idx as u32 + 1),
+                Field::Real(Quantity::Stress.in_model_units(material.youngs_modulus, self.length)),
+                Field::Blank, //G from E and NU
+                Field::Real(material.poisson_ratio),
+                Field::Real(Quantity::Density.in_model_units(material.density, self.length)),
+                material.thermal_expansion.map_or(Field::Blank, Field::Real),
+                material
+                    .reference_temperature
+                    .map_or(Field::Blank, Field::Real),
+            ];
+            self.card("MAT1", fields);
+        }
+
+        //a single TEMPD for all nodes, check_decks makes sure every material has a TREF
+        let mut reference_temperatures = materials
+            .iter()
+            .filter_map(|material| material.reference_temperature);
+        self.reference_temperature = reference_temperatures.next();
+        if let Some(first) = self.reference_temperature
+            && reference_temperatures.any(|other| other != first)
+        {
+            self.warnings.push(format!(
+                "the materials have different reference temperatures, nodes without a Temperature condition are at {} K",
+                first
+            ));
+        }
+    }
+
+    fn write_condition(
+        &mut self,
+        condition: &BoundaryCondition,
+        group: &str,
+        on_solid: bool,
+        elements: &[&Element],
+        nodes: &BTreeMap<u32, f64>, //with their share of a total force, see load_shares
+        faces: &HashMap<Vec<u32>, (u32, u32, Option<u32>)>,
+    ) {
+        //values in the unit system of the model, checked by check_conditions
+        let value = |name: &str| {
+            let param = condition
+                .kind
+                .params()
+                .iter()
+                .find(|param| param.name == name)?;
+            let text = condition.get(name);
+            let si = param.quantity.to_si(text, self.length).ok()?;
+            Some(param.quantity.in_model_units(si, self.length))
+        };
+
+        match condition.kind {
+            BcKind::Fixed => {
+                let mut fixed = String::new();
+                let mut enforced = Vec::new();
+                let mut skipped = Vec::new();
+                for (idx, param) in condition.kind.params().iter().enumerate() {
+                    //Rx, Ry and Rz, some solvers refuse them on grids of solid elements only
+                    if idx >= 3 && !self.rotations {
+                        if value(param.name).is_some() {
+                            skipped.push(param.name);
+                        }
+                        continue;
+                    }
+                    match value(param.name) {
+                        Some(0.0) => fixed += &(idx + 1).to_string(),
+                        Some(displacement) => enforced.push((idx as i64 + 1, displacement)),
+                        None => {}
+                    }
+                }
+                if !skipped.is_empty() {
+                    self.warn(format!(
+                        "{} of Fixed on {} are not written, the grids of solid elements have no rotations",
+                        skipped.join(", "),
+                        group
+                    ));
+                }
+                self.comment(&format!("Fixed on {}", group));
+                if !fixed.is_empty() {
+                    let mut fields = vec![Field::id(SPC_SET), Field::Text(fixed)];
+                    fields.extend(nodes.keys().map(|&node| Field::id(node)));
+                    self.card("SPC1", fields);
+                }
+                for (component, displacement) in enforced {
+                    for &node in nodes.keys() {
+                        self.card(
+                            "SPC",
+                            vec![
+                                Field::id(SPC_SET),
+                                Field::id(node),
+                                Field::Int(component),
+                                Field::Real(displacement),
+                            ],
+                        );
+                    }
+                }
+                self.used_sets.insert(SPC_SET);
+            }
+            BcKind::Pressure | BcKind::Traction
+                if elements.first().and_then(|element| element.dim()) == Some(2) =>
+            {
+                //a traction is written as a pressure of its magnitude along its direction
+                let (pressure, direction) = if condition.kind == BcKind::Pressure {
+                    (value("P").unwrap_or(0.0), None)
+                } else {
+                    let vector = ["Tx", "Ty", "Tz"].map(|name| value(name).unwrap_or(0.0));
+                    let magnitude = vector.iter().map(|t| t * t).sum::<f64>().sqrt();
+                    (magnitude, Some(vector))
+                };
+
+                self.comment(&format!("{} on {}", condition.kind, group));
+                let mut missing = 0;
+                for element in elements {
+                    let (eid, g1, g34) = if on_solid {
+                        let mut corners = element.corners().to_vec();
+                        corners.sort_unstable();
+                        match faces.get(&corners) {
+                            Some(&(eid, g1, g34)) => {
+                                (eid, Field::id(g1), g34.map_or(Field::Blank, Field::id))
+                            }
+                            None => {
+                                missing += 1;
+                                continue;
+                            }
+                        }
+                    } else {
+                        (element.id, Field::Blank, Field::Blank)
+                    };
+
+                    let mut fields = vec![
+                        Field::id(LOAD_SET),
+                        Field::id(eid),
+                        Field::Real(pressure),
+                        Field::Blank,
+                        Field::Blank,
+                        Field::Blank,
+                        g1,
+                        g34,
+                    ];
+                    if let Some(vector) = direction {
+                        fields.push(Field::Int(0));
+                        fields.extend(vector.map(Field::Real));
+                    }
+                    self.card("PLOAD4", fields);
+                }
+                self.used_sets.insert(LOAD_SET);
+
+                if missing > 0 {
+                    self.warn(format!(
+                        "{} faces of {} are not on a solid element, their {} is not written",
+                        missing,
+                        group,
+                        condition.kind.label().to_lowercase()
+                    ));
+                }
+            }
+            BcKind::Force => {
+                let force = ["Fx", "Fy", "Fz"].map(|name| value(name).unwrap_or(0.0));
+                self.comment(&format!("Force on {}", group));
+                for (&node, &share) in nodes {
+                    //eg. the corners of second order triangles
+                    if share.abs() < 1e-12 {
+                        continue;
+                    }
+                    let mut fields = vec![
+                        Field::id(LOAD_SET),
+                        Field::id(node),
+                        Field::Int(0),
+                        Field::Real(1.0),
+                    ];
+                    fields.extend(force.map(|component| Field::Real(component * share)));
+                    self.card("FORCE", fields);
+                }
+                self.used_sets.insert(LOAD_SET);
+            }
+            BcKind::Temperature => {
+                let temperature = value("T").unwrap_or(0.0);
+                self.comment(&format!("Temperature on {}", group));
+                for &node in nodes.keys() {
+                    self.card(
+                        "TEMP",
+                        vec![
+                            Field::id(TEMPERATURE_SET),
+                            Field::id(node),
+                            Field::Real(temperature),
+                        ],
+                    );
+                }
+                self.used_sets.insert(TEMPERATURE_SET);
+            }
+            BcKind::Pressure | BcKind::Traction => self.warn(format!(
+                "{} on {} is not written, edge loads are not supported",
+                condition.kind, group
+            )),
+            BcKind::Convection | BcKind::HeatFlux => self.warn(format!(
+                "{} on {} is not written, it needs a heat transfer analysis",
+                condition.kind, group
+            )),
+        }
+    }
+
+    ///executive and case control, then the bulk data
+    fn render(&self, title: &str) -> String {
+        let mut lines = vec![
+            String::from("$ Nastran deck written by termsh, mesh by Gmsh"),
+            format!(
+                "$ units: N, s, K and {}, stresses in {}, densities in {}",
+                self.length,
+                Quantity::Stress.model_unit(self.length),
+                Quantity::Density.model_unit(self.length)
+            ),
+            String::from("SOL 101"),
+            String::from("CEND"),
+            format!("TITLE = {}", title),
+            String::from("ECHO = NONE"),
+            String::from("SUBCASE 1"),
+            String::from("  LABEL = static"),
+        ];
+        for (set, request) in [
+            (SPC_SET, "SPC"),
+            (LOAD_SET, "LOAD"),
+            (TEMPERATURE_SET, "TEMPERATURE(LOAD)"),
+        ] {
+            if self.used_sets.contains(&set) {
+                lines.push(format!("  {} = {}", request, set));
+            }
+        }
+        lines.push(String::from("  DISPLACEMENT(PLOT) = ALL"));
+        lines.push(String::from("  STRESS(PLOT) = ALL"));
+        lines.push(String::from("BEGIN BULK"));
+        lines.extend(self.lines.iter().cloned());
+        lines.push(String::from("ENDDATA"));
+
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gmsh_ctl::{CurvPhys, ModelDimension, PointPhys, SurfPhys, VolPhys};
+
+    #[test]
+    fn reals_fit_the_field() {
+        assert_eq!(format_real(1.0, 8), "1.");
+        assert_eq!(format_real(-210000.0, 8), "-210000.");
+        assert_eq!(format_real(7.85e-9, 8), "7.85E-9");
+        assert_eq!(format_real(0.123456789, 8), "1.235E-1");
+        assert_eq!(format_real(0.123456789, 16), "0.123456789");
+        for value in [1.0 / 3.0, -123456789.123, 2.5e-12, 6.02e23] {
+            assert!(format_real(value, 8).len() <= 8, "{}", value);
+            assert!(format_real(value, 16).len() <= 16, "{}", value);
+        }
+    }
+
+    #[test]
+    fn reals_without_exponent_letter() {
+        assert_eq!(parse_real("1.5-3"), Some(1.5e-3));
+        assert_eq!(parse_real("-2.5+2"), Some(-250.0));
+        assert_eq!(parse_real(" 4.0D1 "), Some(40.0));
+        assert_eq!(parse_real("12."), Some(12.0));
+        assert_eq!(parse_real("abc"), None);
+    }
+
+    #[test]
+    fn cards_in_each_format() {
+        let grid = [
+            Field::id(7),
+            Field::Blank,
+            Field::Real(1.5),
+            Field::Real(2.5),
+            Field::Real(3.5),
+        ];
+        assert_eq!(
+            FieldFormat::Small.render("GRID", &grid),
+            "GRID    7               1.5     2.5     3.5"
+        );
+        assert_eq!(
+            FieldFormat::Large.render("GRID", &grid),
+            "GRID*   7                               1.5             2.5\n*       3.5"
+        );
+        assert_eq!(
+            FieldFormat::Free.render("GRID", &grid),
+            "GRID,7,,1.5,2.5,3.5"
+        );
+
+        //trailing blanks are dropped, long cards are continued
+        let mut spc1 = vec![Field::id(1), Field::Text(String::from("123"))];
+        spc1.extend((1..=10).map(Field::id));
+        spc1.push(Field::Blank);
+        assert_eq!(
+            FieldFormat::Small.render("SPC1", &spc1),
+            "SPC1    1       123     1       2       3       4       5       6\n\
+             +       7       8       9       10"
+        );
+        assert_eq!(
+            FieldFormat::Free.render("SPC1", &spc1),
+            "SPC1,1,123,1,2,3,4,5,6\n+,7,8,9,10"
+        );
+    }
+
+    #[test]
+    fn rendered_cards_read_back() {
+        let mut fields = vec![Field::id(3), Field::id(1)];
+        fields.extend((1..=10).map(Field::id));
+        let expected: Vec<String> = ["CTETRA", "3", "1"]
+            .into_iter()
+            .map(String::from)
+            .chain((1..=10).map(|node| node.to_string()))
+            .collect();
+        for format in [FieldFormat::Small, FieldFormat::Large, FieldFormat::Free] {
+            let content = format!(
+                "BEGIN BULK\n{}\nENDDATA\n",
+                format.render("CTETRA", &fields)
+            );
+            let cards = read_cards(&content);
+            assert_eq!(cards.len(), 1, "{:?}", format);
+            let card: Vec<String> = cards[0].iter().filter(|f| !f.is_empty()).cloned().collect();
+            assert_eq!(card, expected, "{:?}", format);
+        }
+    }
+
+    //a tetrahedron with a loaded face and a fixed face
+    const MESH: &str = "\
+$ written by Gmsh
+BEGIN BULK
+GRID,1,,0.,0.,0.
+GRID,2,,1.,0.,0.
+GRID,3,,0.,1.,0.
+GRID*   4                               0.              0.
+*       1.
+CTETRA  1       1       1       2       3       4
+CTRIA3,10,2,1,2,3
+CTRIA3,11,3,1,2,4
+ENDDATA
+";
+
+    #[test]
+    fn gmsh_mesh_is_parsed() {
+        let mesh = BulkMesh::parse(MESH).unwrap();
+        assert_eq!(mesh.grids.len(), 4);
+        assert_eq!(mesh.grids[3].coords, [0.0, 0.0, 1.0]);
+        assert_eq!(mesh.elements.len(), 3);
+        assert_eq!(mesh.group_elements(3, 1).len(), 1);
+        assert_eq!(mesh.group_elements(2, 3)[0].nodes, vec![1, 2, 4]);
+        assert_eq!(mesh.grid_at([1.0, 0.0, 1e-13]), Some(2));
+        assert_eq!(mesh.grid_at([0.5, 0.5, 0.0]), None);
+        assert!(BulkMesh::parse("BEGIN BULK\nENDDATA\n").is_err());
+    }
+
+    #[test]
+    fn tetrahedron_faces_point_at_the_opposite_corner() {
+        let mesh = BulkMesh::parse(MESH).unwrap();
+        let faces = mesh.elements[0].solid_faces();
+        assert_eq!(faces.len(), 4);
+        assert_eq!(faces[0], (vec![1, 2, 3], 1, Some(4)));
+        assert_eq!(faces[2], (vec![2, 3, 4], 2, Some(1)));
+    }
+
+    fn assert_shares(content: &str, pid: u32, dim: u8, expected: &[(u32, f64)]) {
+        let mesh = BulkMesh::parse(content).unwrap();
+        let shares = mesh.load_shares(&mesh.group_elements(dim, pid));
+        assert_eq!(shares.len(), expected.len(), "{:?}", shares);
+        for (node, share) in expected {
+            assert!(
+                (shares[node] - share).abs() < 1e-12,
+                "{}: {:?}",
+                node,
+                shares
+            );
+        }
+    }
+
+    #[test]
+    fn force_shares_follow_the_area() {
+        //a unit square and a triangle of half its area, sharing the edge 2-3
+        let content = "\
+BEGIN BULK
+GRID,1,,0.,0.,0.
+GRID,2,,1.,0.,0.
+GRID,3,,1.,1.,0.
+GRID,4,,0.,1.,0.
+GRID,5,,2.,0.,0.
+CQUAD4,1,1,1,2,3,4
+CTRIA3,2,1,2,5,3
+CBAR,3,2,1,2
+CBAR,4,2,2,5
+CBAR,5,2,5,3
+ENDDATA
+";
+        //the quadrangle carries 2/3 of the force, the triangle 1/3
+        let (quad, tria) = (2.0 / 3.0 / 4.0, 1.0 / 3.0 / 3.0);
+        assert_shares(
+            content,
+            1,
+            2,
+            &[
+                (1, quad),
+                (2, quad + tria),
+                (3, quad + tria),
+                (4, quad),
+                (5, tria),
+            ],
+        );
+
+        //edges of length 1, 1 and sqrt(2)
+        let total = 2.0 + 2f64.sqrt();
+        assert_shares(
+            content,
+            2,
+            1,
+            &[
+                (1, 0.5 / total),
+                (2, 1.0 / total),
+                (5, (0.5 + 2f64.sqrt() / 2.0) / total),
+                (3, 2f64.sqrt() / 2.0 / total),
+            ],
+        );
+    }
+
+    #[test]
+    fn force_shares_on_second_order_elements() {
+        let tria6 = "\
+BEGIN BULK
+GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+GRID,3,,0.,2.,0.
+GRID,4,,1.,0.,0.
+GRID,5,,1.,1.,0.
+GRID,6,,0.,1.,0.
+CTRIA6,1,1,1,2,3,4,5,6
+ENDDATA
+";
+        let third = 1.0 / 3.0;
+        assert_shares(
+            tria6,
+            1,
+            2,
+            &[
+                (1, 0.0),
+                (2, 0.0),
+                (3, 0.0),
+                (4, third),
+                (5, third),
+                (6, third),
+            ],
+        );
+
+        let quad8 = "\
+BEGIN BULK
+GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+GRID,3,,2.,2.,0.
+GRID,4,,0.,2.,0.
+GRID,5,,1.,0.,0.
+GRID,6,,2.,1.,0.
+GRID,7,,1.,2.,0.
+GRID,8,,0.,1.,0.
+CQUAD8,1,1,1,2,3,4,5,6
++,7,8
+ENDDATA
+";
+        let (corner, midside) = (-1.0 / 12.0, third);
+        assert_shares(
+            quad8,
+            1,
+            2,
+            &[
+                (1, corner),
+                (2, corner),
+                (3, corner),
+                (4, corner),
+                (5, midside),
+                (6, midside),
+                (7, midside),
+                (8, midside),
+            ],
+        );
+    }
+
+    fn condition(kind: BcKind, params: &[(&str, &str)]) -> BoundaryCondition {
+        let mut condition = BoundaryCondition::new(kind);
+        for (name, value) in params {
+            condition.set(name, value.to_string());
+        }
+        condition
+    }
+
+    #[test]
+    fn deck_cards() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        gmsh_para.vol_phy_list.push(VolPhys {
+            name: String::from("body"),
+            phys_id: String::from("1"),
+            vol_ids: "1".parse().unwrap(),
+            mesh_size: String::new(),
+            material: String::from("Steel"),
+        });
+        for (name, phys_id, conditions) in [
+            (
+                "top",
+                "2",
+                vec![
+                    condition(BcKind::Pressure, &[("P", "2 MPa")]),
+                    condition(BcKind::Temperature, &[("T", "100 C")]),
+                ],
+            ),
+            (
+                "base",
+                "3",
+                vec![condition(
+                    BcKind::Fixed,
+                    &[("Ux", "0"), ("Uy", "0"), ("Uz", "0"), ("Rz", "0")],
+                )],
+            ),
+        ] {
+            gmsh_para.surf_phy_list.push(SurfPhys {
+                name: String::from(name),
+                phys_id: String::from(phys_id),
+                surf_ids: "1".parse().unwrap(),
+                mesh_size: String::new(),
+                boundary_conditions: conditions,
+                material: String::new(),
+                thickness: String::new(),
+            });
+        }
+        gmsh_para.point_phy_list.push(PointPhys {
+            name: String::from("tip"),
+            phys_id: String::from("9"),
+            point_ids: "5, 6".parse().unwrap(),
+            boundary_conditions: vec![condition(BcKind::Force, &[("Fz", "-10")])],
+        });
+
+        let mesh = BulkMesh::parse(MESH).unwrap();
+        let points = HashMap::from([(5, [0.0, 0.0, 1.0]), (6, [0.5, 0.5, 0.5])]);
+        let mut deck = Deck::new(FieldFormat::Small, LengthUnit::Mm);
+        deck.write(&gmsh_para, &MaterialLibrary::builtin(), &mesh, &points);
+        let content = deck.render("test");
+        let lines: Vec<&str> = content.lines().collect();
+
+        for expected in [
+            "PSOLID  1       1",
+            "MAT1    1       210000.         0.3     7.85E-9 1.2E-5  293.15",
+            "PLOAD4  2       1       2.                              1       4",
+            "TEMP    3       1       373.15",
+            "SPC1    1       123     1       2       4",
+            "FORCE   2       4       0       1.      0.      0.      -10.",
+            "TEMPD   3       293.15",
+            "  SPC = 1",
+            "  LOAD = 2",
+            "  TEMPERATURE(LOAD) = 3",
+        ] {
+            assert!(lines.contains(&expected), "{}\n{}", expected, content);
+        }
+        //the boundary elements only locate the conditions
+        assert!(!content.contains("CTRIA3"));
+        assert_eq!(
+            deck.warnings,
+            vec![
+                String::from(
+                    "Rz of Fixed on Surface base are not written, the grids of solid elements have no rotations"
+                ),
+                String::from("Point tip: point 6 has no node in the mesh"),
+            ]
+        );
+    }
+
+    #[test]
+    fn decks_refuse_invalid_conditions() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.model_dimension = ModelDimension::Solid;
+        gmsh_para.export_settings[0].nastran_deck = true;
+        gmsh_para.point_phy_list.push(PointPhys {
+            name: String::from("tip"),
+            phys_id: String::from("9"),
+            point_ids: "5".parse().unwrap(),
+            boundary_conditions: vec![condition(BcKind::Force, &[("Fz", "-10 kg")])],
+        });
+
+        let error = gmsh_para
+            .check_decks(&MaterialLibrary::builtin())
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("group tip: Fz:"), "{}", error);
+    }
+
+    #[test]
+    fn decks_refuse_edge_loads() {
+        let mut gmsh_para = GmshPara::new();
+        gmsh_para.model_dimension = ModelDimension::Shell;
+        gmsh_para.export_settings[0].nastran_deck = true;
+        gmsh_para.export_settings[0].element_tag_type = 2;
+        gmsh_para.curve_phy_list.push(CurvPhys {
+            name: String::from("edge"),
+            phys_id: String::from("4"),
+            curve_ids: "2".parse().unwrap(),
+            boundary_layer: false,
+            boundary_conditions: vec![condition(BcKind::Pressure, &[("P", "1 MPa")])],
+        });
+        //accepted for the mesh, the deck would leave it out
+        assert!(gmsh_para.check_conditions().is_ok());
+
+        let error = gmsh_para
+            .check_decks(&MaterialLibrary::builtin())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("Pressure on Physical Curve edge is an edge load"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    fs::{self, read_to_string},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::error::{Result, TermshError};
+use crate::geometry::GeometryPart;
+use crate::gmsh_ctl::GmshPara;
+
+///default project file in current folder, used when --project is not given
+pub const DEFAULT_PROJECT_FILE: &str = "termsh_project.json";
+
+///the old termsh_cache.json, only holds Physical Names and Physical IDs
+pub const LEGACY_CACHE_FILE: &str = "termsh_cache.json";
+
+///bump this when the layout of the project file changes, and add a step in migrate()
+pub const PROJECT_VERSION: u32 = 3;
+
+#[derive(Serialize, Deserialize)]
+struct Project {
+    version: u32,
+    gmsh_para: GmshPara,
+}
+
+///Load the project file, older layouts are migrated to the current version
+pub fn load_project(filename: &str) -> Result<GmshPara> {
+    let content = read_to_string(filename).map_err(TermshError::io(format!(
+        "failed to read project {}",
+        filename
+    )))?;
+    let invalid = |reason: String| {
+        TermshError::Project(format!("project {} is invalid: {}", filename, reason))
+    };
+
+    let value: Value = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+    let value = migrate(value).map_err(invalid)?;
+    let mut project: Project = serde_json::from_value(value).map_err(|e| invalid(e.to_string()))?;
+    project.gmsh_para.complete_export_settings();
+
+    Ok(project.gmsh_para)
+}
+
+///Load the project file if it exists, otherwise fall back to the legacy cache, otherwise start empty
+pub fn load_project_or_default(filename: &str) -> Result<GmshPara> {
+    if Path::new(filename).exists() {
+        load_project(filename)
+    } else if filename == DEFAULT_PROJECT_FILE && Path::new(LEGACY_CACHE_FILE).exists() {
+        load_project(LEGACY_CACHE_FILE)
+    } else {
+        Ok(GmshPara::new())
+    }
+}
+
+pub fn save_project(filename: &str, gmsh_para: &GmshPara) -> Result<()> {
+    let project = Project {
+        version: PROJECT_VERSION,
+        gmsh_para: gmsh_para.clone(),
+    };
+
+    let json_str =
+        serde_json::to_string_pretty(&project).map_err(|e| TermshError::Project(e.to_string()))?;
+    fs::write(filename, json_str).map_err(TermshError::io(format!("failed to save {}", filename)))
+}
+
+///Bring a project of any known layout up to PROJECT_VERSION, one version at a time
+//the error is the reason only, load_project() tells which file it is
+fn migrate(mut value: Value) -> std::result::Result<Value, String> {
+    loop {
+        let version = match value.get("version") {
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| String::from("project version is not a number"))?,
+            None => 0, //termsh_cache.json has no version field
+        };
+
+        value = match version {
+            0 => migrate_v0_to_v1(value)?,
+            1 => migrate_v1_to_v2(value),
+            2 => migrate_v2_to_v3(value),
+            v if v == PROJECT_VERSION as u64 => return Ok(value),
+            v => {
+                return Err(format!(
+                    "project version {} is newer than supported version {}",
+                    v, PROJECT_VERSION
+                ));
+            }
+        };
+    }
+}
+
+#[derive(Deserialize)]
+struct PhysPair {
+    name: String,
+    phys_id: String,
+}
+
+//layout of the old termsh_cache.json
+#[derive(Deserialize)]
+struct PhysPairList {
+    vol_pairs: Vec<PhysPair>,
+    sur_pairs: Vec<PhysPair>,
+}
+
+//v0 only had the names and ids, v1 is the first GmshPara layout: one geometry_file,
+//entity ids as typed and mesh_paras.max_size
+fn migrate_v0_to_v1(value: Value) -> std::result::Result<Value, String> {
+    let phys_pair_list: PhysPairList = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    let groups = |pairs: Vec<PhysPair>, ids_key: &str| -> Vec<Value> {
+        pairs
+            .into_iter()
+            .map(|pair| json!({"name": pair.name, "phys_id": pair.phys_id, ids_key: ""}))
+            .collect()
+    };
+
+    Ok(json!({
+        "version": 1,
+        "gmsh_para": {
+            "geometry_file": "",
+            "vol_phy_list": groups(phys_pair_list.vol_pairs, "vol_ids"),
+            "surf_phy_list": groups(phys_pair_list.sur_pairs, "surf_ids"),
+            "mesh_paras": {"max_size": ""},
+        },
+    }))
+}
+
+//v1 had a single geometry_file, v2 has a list of geometry_parts
+fn migrate_v1_to_v2(mut value: Value) -> Value {
+    if let Some(gmsh_para) = value.get_mut("gmsh_para").and_then(Value::as_object_mut)
+        && let Some(Value::String(geometry_file)) = gmsh_para.remove("geometry_file")
+    {
+        let parts = match geometry_file.is_empty() {
+            true => Vec::new(),
+            false => vec![GeometryPart::new(geometry_file)],
+        };
+        gmsh_para.insert(String::from("geometry_parts"), json!(parts));
+    }
+
+    value["version"] = json!(2);
+    value
+}
+
+//v2 had only mesh_paras.max_size, v3 keeps mesh options by their Gmsh name
+fn migrate_v2_to_v3(mut value: Value) -> Value {
+    if let Some(mesh_paras) = value
+        .pointer_mut("/gmsh_para/mesh_paras")
+        .and_then(Value::as_object_mut)
+        && let Some(Value::String(max_size)) = mesh_paras.remove("max_size")
+    {
+        let mut options = serde_json::Map::new();
+        if !max_size.is_empty() {
+            options.insert(String::from("Mesh.MeshSizeMax"), json!(max_size));
+        }
+        mesh_paras.insert(String::from("options"), Value::Object(options));
+    }
+
+    value["version"] = json!(3);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v0_cache_migrates_through_every_version() {
+        let fixture = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/termsh_cache_v0.json"
+        );
+        let gmsh_para = load_project(fixture).unwrap();
+
+        assert!(gmsh_para.geometry_parts.is_empty());
+        let vol_names: Vec<&str> = gmsh_para
+            .vol_phy_list
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(vol_names, ["housing", "shaft"]);
+        assert_eq!(gmsh_para.vol_phy_list[1].phys_id, "2");
+        assert!(gmsh_para.vol_phy_list[0].vol_ids.is_empty());
+        assert_eq!(gmsh_para.surf_phy_list[0].name, "inlet");
+        assert!(gmsh_para.mesh_paras.options.is_empty());
+    }
+
+    #[test]
+    fn v1_steps_change_one_version_each() {
+        let v1 = migrate_v0_to_v1(json!({"vol_pairs": [], "sur_pairs": []})).unwrap();
+        assert_eq!(v1["version"], 1);
+        assert_eq!(v1["gmsh_para"]["geometry_file"], "");
+
+        let mut v1 = v1;
+        v1["gmsh_para"]["geometry_file"] = json!("a.step");
+        v1["gmsh_para"]["mesh_paras"]["max_size"] = json!("5");
+        let v2 = migrate_v1_to_v2(v1);
+        assert_eq!(v2["version"], 2);
+        assert_eq!(v2["gmsh_para"]["geometry_parts"][0]["file"], "a.step");
+        assert_eq!(v2["gmsh_para"]["mesh_paras"]["max_size"], "5");
+
+        let v3 = migrate_v2_to_v3(v2);
+        assert_eq!(v3["version"], 3);
+        assert_eq!(
+            v3["gmsh_para"]["mesh_paras"]["options"]["Mesh.MeshSizeMax"],
+            "5"
+        );
+    }
+}
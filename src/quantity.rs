@@ -0,0 +1,198 @@
+use std::f64::consts::PI;
+
+use crate::geometry::LengthUnit;
+
+///Physical quantities of boundary condition values and material properties
+///a value is typed with a unit (eg. "2 MPa") or as a bare number in the unit system of the model,
+///which is N, s, K and the target length unit, so stresses are in MPa for a model in mm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Length,
+    Angle,
+    Stress, //pressure and traction
+    Force,
+    Temperature,
+    HeatTransfer, //convection coefficient
+    HeatFlux,
+    Density, //only converted for the Nastran deck, not typed
+}
+
+//units which can be typed after the number, with their factor to SI
+const LENGTH_UNITS: &[(&str, f64)] = &[
+    ("m", 1.0),
+    ("cm", 1e-2),
+    ("mm", 1e-3),
+    ("um", 1e-6),
+    ("in", 0.0254),
+    ("ft", 0.3048),
+];
+const ANGLE_UNITS: &[(&str, f64)] = &[("rad", 1.0), ("deg", PI / 180.0)];
+const STRESS_UNITS: &[(&str, f64)] = &[
+    ("Pa", 1.0),
+    ("kPa", 1e3),
+    ("MPa", 1e6),
+    ("GPa", 1e9),
+    ("bar", 1e5),
+    ("psi", 6894.757),
+];
+const FORCE_UNITS: &[(&str, f64)] = &[("N", 1.0), ("kN", 1e3), ("MN", 1e6), ("lbf", 4.448222)];
+const TEMPERATURE_UNITS: &[(&str, f64)] = &[("K", 1.0), ("C", 1.0)]; //C is shifted, see to_si
+const HEAT_TRANSFER_UNITS: &[(&str, f64)] = &[("W/m2K", 1.0), ("kW/m2K", 1e3), ("W/mm2K", 1e6)];
+const HEAT_FLUX_UNITS: &[(&str, f64)] = &[("W/m2", 1.0), ("kW/m2", 1e3), ("W/mm2", 1e6)];
+const DENSITY_UNITS: &[(&str, f64)] = &[("kg/m3", 1.0), ("g/cm3", 1e3), ("t/mm3", 1e12)];
+
+const ZERO_CELSIUS: f64 = 273.15;
+
+impl Quantity {
+    pub fn units(self) -> &'static [(&'static str, f64)] {
+        match self {
+            Quantity::Length => LENGTH_UNITS,
+            Quantity::Angle => ANGLE_UNITS,
+            Quantity::Stress => STRESS_UNITS,
+            Quantity::Force => FORCE_UNITS,
+            Quantity::Temperature => TEMPERATURE_UNITS,
+            Quantity::HeatTransfer => HEAT_TRANSFER_UNITS,
+            Quantity::HeatFlux => HEAT_FLUX_UNITS,
+            Quantity::Density => DENSITY_UNITS,
+        }
+    }
+
+    ///SI value of one model unit, with f the target length unit in meters
+    ///power is N·length/s, so heat fluxes and coefficients scale with 1/f
+    ///mass is N·s²/length, so densities scale with 1/f⁴
+    fn model_factor(self, length: LengthUnit) -> f64 {
+        let f = length.in_meters();
+        match self {
+            Quantity::Length => f,
+            Quantity::Stress => 1.0 / (f * f),
+            Quantity::HeatTransfer | Quantity::HeatFlux => 1.0 / f,
+            Quantity::Density => 1.0 / (f * f * f * f),
+            Quantity::Angle | Quantity::Force | Quantity::Temperature => 1.0,
+        }
+    }
+
+    ///unit of a bare number, eg. "MPa" for a stress in a model in mm
+    pub fn model_unit(self, length: LengthUnit) -> String {
+        match (self, length) {
+            (Quantity::Length, _) => length.to_string(),
+            (Quantity::Angle, _) => String::from("rad"),
+            (Quantity::Stress, LengthUnit::M) => String::from("Pa"),
+            (Quantity::Stress, LengthUnit::Mm) => String::from("MPa"),
+            (Quantity::Stress, _) => format!("N/{}2", length),
+            (Quantity::Force, _) => String::from("N"),
+            (Quantity::Temperature, _) => String::from("K"),
+            (Quantity::HeatTransfer, LengthUnit::M) => String::from("W/m2K"),
+            (Quantity::HeatTransfer, LengthUnit::Mm) => String::from("kW/m2K"),
+            (Quantity::HeatTransfer, _) => format!("N/(s {} K)", length),
+            (Quantity::HeatFlux, LengthUnit::M) => String::from("W/m2"),
+            (Quantity::HeatFlux, LengthUnit::Mm) => String::from("kW/m2"),
+            (Quantity::HeatFlux, _) => format!("N/(s {})", length),
+            (Quantity::Density, LengthUnit::M) => String::from("kg/m3"),
+            (Quantity::Density, LengthUnit::Mm) => String::from("t/mm3"),
+            (Quantity::Density, _) => format!("N s2/{}4", length),
+        }
+    }
+
+    ///Value of a typed text in SI units (K for temperatures)
+    pub fn to_si(self, text: &str, length: LengthUnit) -> Result<f64, String> {
+        let text = text.trim();
+        if let Ok(value) = text.parse::<f64>() {
+            return Ok(value * self.model_factor(length));
+        }
+
+        for (unit, factor) in self.units() {
+            if let Some(number) = text.strip_suffix(unit)
+                && let Ok(value) = number.trim().parse::<f64>()
+            {
+                return Ok(match (self, *unit) {
+                    (Quantity::Temperature, "C") => value + ZERO_CELSIUS,
+                    _ => value * factor,
+                });
+            }
+        }
+
+        let units: Vec<&str> = self.units().iter().map(|(unit, _)| *unit).collect();
+        Err(format!(
+            "\"{}\" is not a number with a unit, expected eg. 1 {} (units: {})",
+            text,
+            units[0],
+            units.join(", ")
+        ))
+    }
+
+    ///SI value (K for temperatures) in the unit system of the model
+    pub fn in_model_units(self, value: f64, length: LengthUnit) -> f64 {
+        value / self.model_factor(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn typed_units_to_si() {
+        let mm = LengthUnit::Mm;
+        assert!(close(Quantity::Stress.to_si("2 bar", mm).unwrap(), 2e5));
+        assert!(close(Quantity::Stress.to_si("3MPa", mm).unwrap(), 3e6));
+        assert!(close(Quantity::Force.to_si("5 kN", mm).unwrap(), 5e3));
+        assert!(close(Quantity::Length.to_si("1 in", mm).unwrap(), 0.0254));
+        assert!(close(
+            Quantity::HeatTransfer.to_si("25 kW/m2K", mm).unwrap(),
+            25e3
+        ));
+        assert!(close(
+            Quantity::Temperature.to_si("20 C", mm).unwrap(),
+            293.15
+        ));
+        assert!(close(
+            Quantity::Temperature.to_si("300 K", mm).unwrap(),
+            300.0
+        ));
+    }
+
+    #[test]
+    fn bare_numbers_are_in_model_units() {
+        //MPa, mm and kW/m2 for a model in mm
+        let mm = LengthUnit::Mm;
+        assert!(close(Quantity::Stress.to_si("2", mm).unwrap(), 2e6));
+        assert!(close(Quantity::Length.to_si("4", mm).unwrap(), 4e-3));
+        assert!(close(Quantity::HeatFlux.to_si("1", mm).unwrap(), 1e3));
+        assert!(close(
+            Quantity::Stress.to_si("2", LengthUnit::M).unwrap(),
+            2.0
+        ));
+        assert!(close(Quantity::Temperature.to_si("20", mm).unwrap(), 20.0));
+    }
+
+    #[test]
+    fn wrong_units_are_refused() {
+        let mm = LengthUnit::Mm;
+        assert!(Quantity::Stress.to_si("2 kN", mm).is_err());
+        assert!(Quantity::Force.to_si("", mm).is_err());
+        assert!(Quantity::Length.to_si("mm", mm).is_err());
+        let error = Quantity::Force.to_si("x", mm).unwrap_err();
+        assert!(error.contains("N, kN, MN, lbf"), "{}", error);
+    }
+
+    #[test]
+    fn si_back_to_model_units() {
+        let mm = LengthUnit::Mm;
+        assert!(close(Quantity::Stress.in_model_units(210e9, mm), 210e3));
+        assert!(close(Quantity::Density.in_model_units(7850.0, mm), 7.85e-9));
+        assert!(close(
+            Quantity::Density.in_model_units(7850.0, LengthUnit::M),
+            7850.0
+        ));
+        assert!(close(
+            Quantity::Temperature.in_model_units(293.15, mm),
+            293.15
+        ));
+        assert_eq!(Quantity::Density.model_unit(mm), "t/mm3");
+        assert_eq!(Quantity::Stress.model_unit(LengthUnit::Cm), "N/cm2");
+    }
+}
@@ -1,6 +1,10 @@
 use std::fs;
 use std::io;
+use std::path::Path;
 use std::process::Child;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEventKind;
@@ -12,106 +16,399 @@ use ratatui::prelude::*;
 use ratatui::symbols::merge::MergeStrategy;
 use ratatui::widgets::*;
 
+use crate::boundary_condition::{BcKind, BoundaryCondition};
+use crate::boundary_layer::BoundaryLayer;
+use crate::coverage::check_coverage;
+use crate::entity::EntityList;
+use crate::error::{Result, TermshError};
+use crate::field::{FieldKind, FieldParam, MeshField, ParamKind};
+use crate::geometry::{GeometryPart, LengthUnit, validate_parts};
 use crate::gmsh_ctl::*;
+use crate::gmsh_log::{GmshLog, LogLevel};
+use crate::gmsh_option::{
+    GmshOption, MESH_OPTIONS, OptionKind, complete_option, find_option, validate_advanced,
+};
+use crate::id_set::IdSet;
+use crate::material::MaterialLibrary;
+use crate::nastran::write_decks;
+use crate::project::save_project;
+
+pub fn termsh_run(
+    gmsh_para: GmshPara,
+    material_library: MaterialLibrary,
+    project_file: String,
+) -> Result<()> {
+    let mut tui = Tui::new(gmsh_para, material_library, project_file);
+
+    //find out which volumes and surfaces exist, in background
+    tui.start_entity_query();
+
+    //start a Gmsh Child Process first to visualize the geometry, a failure shows up as popup
+    tui.restart_gmsh(ScriptAction::Preview);
+
+    let tui_res =
+        ratatui::run(|terminal| tui.run(terminal)).map_err(TermshError::io("terminal error"));
+
+    //every step below runs even if one fails, the first error is returned
+    //kill Gmsh Child Process
+    let kill_res = tui.stop_gmsh();
+
+    //Save the whole project when exiting the program
+    let save_res = save_project(&tui.project_file, &tui.gmesh_para);
+
+    // clean up temporary file, it does not exist when the script could not be written
+    let temp_file_name = tui.gmesh_para.temp_script_file_name(ScriptAction::Preview);
+    let clean_res = match Path::new(&temp_file_name).exists() {
+        true => fs::remove_file(&temp_file_name).map_err(TermshError::io(format!(
+            "failed to clean up {}",
+            temp_file_name
+        ))),
+        false => Ok(()),
+    };
+
+    tui_res.and(kill_res).and(save_res).and(clean_res)
+}
 
-pub fn termsh_run(geometry_filename: String) -> io::Result<()> {
-    let mut tui = TUI::new();
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TypeMode {
+    None,
+    Volume,
+    Surface,
+    Curve,
+    Point,
+    Entity,
+    Part,
+    Geometry,
+    Mesh,
+    Advanced,
+    Field,
+    Layer,
+    Condition,
+    Export,
+    Log,
+}
 
-    //assigning geometry file name
-    tui.gmesh_para.geometry_file = geometry_filename;
+impl TypeMode {
+    //order of the tables when moving with ←→
+    const CYCLE: [TypeMode; 15] = [
+        TypeMode::None,
+        TypeMode::Volume,
+        TypeMode::Surface,
+        TypeMode::Curve,
+        TypeMode::Point,
+        TypeMode::Entity,
+        TypeMode::Part,
+        TypeMode::Geometry,
+        TypeMode::Mesh,
+        TypeMode::Advanced,
+        TypeMode::Field,
+        TypeMode::Layer,
+        TypeMode::Condition,
+        TypeMode::Export,
+        TypeMode::Log,
+    ];
+
+    fn left(self) -> TypeMode {
+        let idx = Self::CYCLE.iter().position(|t| *t == self).unwrap();
+        Self::CYCLE[(idx + Self::CYCLE.len() - 1) % Self::CYCLE.len()]
+    }
 
-    //start a Gmsh Child Process first to visualize the geometry
-    let (gmsh_handle_result, temp_file_name) = tui.gmesh_para.apply_mesh();
+    fn right(self) -> TypeMode {
+        let idx = Self::CYCLE.iter().position(|t| *t == self).unwrap();
+        Self::CYCLE[(idx + 1) % Self::CYCLE.len()]
+    }
 
-    if let Ok(gmsh_handle) = gmsh_handle_result {
-        tui.gmsh_handle = Some(gmsh_handle);
+    //tables sharing the right column, one shown at a time
+    const SETTINGS_TABS: [(TypeMode, &'static str); 8] = [
+        (TypeMode::Part, "Parts"),
+        (TypeMode::Geometry, "Geometry"),
+        (TypeMode::Mesh, "Mesh"),
+        (TypeMode::Advanced, "Advanced"),
+        (TypeMode::Field, "Fields"),
+        (TypeMode::Layer, "Layers"),
+        (TypeMode::Condition, "BCs"),
+        (TypeMode::Export, "Export"),
+    ];
+
+    fn is_settings_tab(self) -> bool {
+        Self::SETTINGS_TABS.iter().any(|(mode, _)| *mode == self)
     }
+}
 
-    let tui_res = ratatui::run(|terminal| tui.run(terminal));
+//rows of the Geometry table, healing options, units and the model dimension
+const GEOMETRY_OPTIONS: [&str; 11] = [
+    "BooleanFragments",
+    "Coherence",
+    "Tolerance",
+    "Fix Degenerated",
+    "Fix Small Edges",
+    "Fix Small Faces",
+    "Sew Faces",
+    "Make Solids",
+    "Source Unit (BREP/.geo)",
+    "Target Unit",
+    "Model Dimension",
+];
+//the rows which are not on/off
+const TOLERANCE_ROW: usize = 2;
+const SOURCE_UNIT_ROW: usize = 8;
+const TARGET_UNIT_ROW: usize = 9;
+const DIMENSION_ROW: usize = 10;
+
+//rows of the Layers table, boundary layer parameters
+const LAYER_OPTIONS: [&str; 4] = [
+    "Wall Curves",
+    "First Thickness",
+    "Growth Ratio",
+    "Layer Count",
+];
+const WALL_CURVES_ROW: usize = 0;
+
+//longest list shown under a field in the Modify popup, it scrolls beyond
+const DROPDOWN_ROWS: usize = 8;
+
+///What the Gmsh Child Process is doing, shown at the bottom
+enum GmshStatus {
+    Idle,
+    Meshing { since: Instant },
+    Done { took: Duration },
+    Failed { reason: String },
+    WindowClosed,
+    Cancelled,
+}
 
-    //kill Gmsh Child Process
-    if let Some(mut gmsh_child) = tui.gmsh_handle.take() {
-        if let Err(e) = gmsh_child.kill() {
-            panic!("Failed to kill Gmsh: {}", e)
+impl GmshStatus {
+    fn line(&self) -> Line<'static> {
+        match self {
+            GmshStatus::Idle => Line::from("Gmsh: idle"),
+            GmshStatus::Meshing { since } => Line::from(format!(
+                "Gmsh: meshing {:.1}s (C: cancel)",
+                since.elapsed().as_secs_f64()
+            ))
+            .yellow(),
+            GmshStatus::Done { took } => {
+                Line::from(format!("Gmsh: done in {:.1}s", took.as_secs_f64())).green()
+            }
+            GmshStatus::Failed { reason } => Line::from(format!("Gmsh: failed, {}", reason)).red(),
+            GmshStatus::WindowClosed => Line::from("Gmsh: window closed"),
+            GmshStatus::Cancelled => Line::from("Gmsh: cancelled").yellow(),
+        }
+    }
+}
+
+enum OperaMode {
+    Select,
+    Modify,
+    Preview, //showing the script that will be sent to Gmsh
+}
+
+//(value, meaning) entries listed under a field
+type Dropdown = Vec<(String, String)>;
+
+///One text box in the Modify popup
+struct InputField {
+    title: String,
+    value: String,
+    width: Constraint,
+    accept: fn(char) -> bool, //which chars can be typed in
+    editable: bool,
+    choices: Dropdown,                     //picked with ↑↓, empty for typed fields
+    suggest: Option<fn(&str) -> Dropdown>, //completions of the typed text
+    suggestion_idx: Option<usize>,         //completion chosen with ↑↓, taken with Tab
+}
+
+impl InputField {
+    fn new(title: impl Into<String>, value: String, accept: fn(char) -> bool) -> Self {
+        InputField {
+            title: title.into(),
+            value,
+            width: Constraint::Percentage(20),
+            accept,
+            editable: true,
+            choices: Vec::new(),
+            suggest: None,
+            suggestion_idx: None,
         }
     }
 
-    //Save Physical Name and Physical IDs to termsh_cache.json
-    tui.gmesh_para.save_cache();
+    fn fill(mut self) -> Self {
+        self.width = Constraint::Fill(1);
+        self
+    }
+
+    fn width(mut self, width: Constraint) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn read_only(mut self) -> Self {
+        self.editable = false;
+        self
+    }
+
+    //the value is picked from the list instead of typed
+    fn choices(mut self, choices: Dropdown) -> Self {
+        self.choices = choices;
+        self.accept = |_| false;
+        self
+    }
+
+    fn suggest(mut self, suggest: fn(&str) -> Dropdown) -> Self {
+        self.suggest = Some(suggest);
+        self
+    }
 
-    // clean up temporary file
-    if let Err(e) = fs::remove_file(temp_file_name) {
-        panic!("Failed to clean up: {}", e)
+    //list shown under the field, with the highlighted entry
+    fn dropdown(&self) -> (Dropdown, Option<usize>) {
+        if !self.choices.is_empty() {
+            let picked = self
+                .choices
+                .iter()
+                .position(|(value, _)| *value == self.value);
+            return (self.choices.clone(), picked);
+        }
+        match self.suggest {
+            Some(suggest) => (suggest(&self.value), self.suggestion_idx),
+            None => (Vec::new(), None),
+        }
     }
 
-    tui_res
+    fn display_value(&self) -> String {
+        match self.choices.iter().find(|(value, _)| *value == self.value) {
+            Some((value, meaning)) if value.is_empty() => meaning.clone(),
+            Some((value, meaning)) => format!("{} ({})", value, meaning),
+            None => self.value.clone(),
+        }
+    }
 }
 
-enum TypeMode {
-    None,
-    Volume,
-    Surface,
-    Mesh,
+fn any_char(_: char) -> bool {
+    true
 }
 
-enum OperaMode {
-    Select,
-    Modify,
+fn digit_char(ch: char) -> bool {
+    ch.is_ascii_digit()
 }
 
-enum ModifyType {
-    VolName,
-    VolPID,
-    VolVID,
-    SurName,
-    SurPID,
-    SurSID,
-    MeshVal,
-    None,
+fn id_list_char(ch: char) -> bool {
+    ch.is_ascii_digit() || [',', ':', '-', ' '].contains(&ch)
+}
+
+fn number_char(ch: char) -> bool {
+    ch.is_ascii_digit() || ch == '.'
+}
+
+fn signed_number_char(ch: char) -> bool {
+    number_char(ch) || ch == '-'
+}
+
+//also allows exponents, eg. 1e-6
+fn float_char(ch: char) -> bool {
+    signed_number_char(ch) || ['e', 'E', '+'].contains(&ch)
+}
+
+fn unit_char(ch: char) -> bool {
+    ch.is_ascii_alphabetic()
+}
+
+fn yes_no_char(ch: char) -> bool {
+    ch == 'y' || ch == 'n'
 }
+
 struct Cursor {
     begin_x: u16,
     begin_y: u16,
     char_idx: u16,
-    modify_type: ModifyType,
+    field_idx: usize, //which InputField is being modified
 } //the actual x=begin_x + char_idx, y=begin_y
 
-pub struct TUI {
+//char_idx counts chars, String::insert and remove take byte offsets
+fn byte_idx(value: &str, char_idx: u16) -> usize {
+    value
+        .char_indices()
+        .nth(char_idx as usize)
+        .map_or(value.len(), |(idx, _)| idx)
+}
+
+//how often the screen refreshes without user input
+const TICK: Duration = Duration::from_millis(100);
+
+pub struct Tui {
     exit: bool,
     gmesh_para: GmshPara,
     table_state: TableState, //this state is shared
     cur_type: TypeMode,
 
-    input_buf: Vec<String>,
+    input_buf: Vec<InputField>,
+    input_error: Option<String>, //why the last confirmed input was rejected
     opreation_mode: OperaMode,
 
     cursor: Cursor,
     gmsh_handle: Option<Child>,
+    gmsh_log: GmshLog, //output of the running Gmsh
+    gmsh_status: GmshStatus,
+    gmsh_done_markers: Vec<String>, //log texts telling that the running script is finished
+    gmsh_exported: Option<GmshPara>, //settings of the running export, its Nastran decks are written when done
+    gmsh_pending: Option<ScriptAction>, //started once the entity query has found the model dimension
+
+    material_library: MaterialLibrary, //materials picked for the volume groups
+    project_file: String,
+    message: String, //one line feedback shown at the bottom, eg. project saved
+
+    preview_script: String,
+    preview_scroll: u16,
+
+    settings_tab: TypeMode, //which table of SETTINGS_TABS the right column shows
+
+    entity_list: Option<EntityList>, //None until the first query finishes
+    entity_query: Option<Receiver<Result<EntityList>>>, //Some while a query is running
+    entity_error: Option<String>,
+
+    error_popup: Option<TermshError>, //shown above everything until dismissed
 }
 
-impl TUI {
-    fn new() -> Self {
-        TUI {
+impl Tui {
+    fn new(gmsh_para: GmshPara, material_library: MaterialLibrary, project_file: String) -> Self {
+        Tui {
             exit: false,
-            gmesh_para: GmshPara::new(),
+            gmesh_para: gmsh_para,
             table_state: TableState::new(),
             cur_type: TypeMode::None,
             input_buf: Vec::new(),
+            input_error: None,
             opreation_mode: OperaMode::Select,
             cursor: Cursor {
                 begin_x: 0,
                 begin_y: 0,
                 char_idx: 0,
-                modify_type: ModifyType::None,
+                field_idx: 0,
             },
             gmsh_handle: None,
+            gmsh_log: GmshLog::new(),
+            gmsh_status: GmshStatus::Idle,
+            gmsh_done_markers: Vec::new(),
+            gmsh_exported: None,
+            gmsh_pending: None,
+            material_library,
+            project_file,
+            message: String::new(),
+            preview_script: String::new(),
+            preview_scroll: 0,
+            settings_tab: TypeMode::Part,
+            entity_list: None,
+            entity_query: None,
+            entity_error: None,
+            error_popup: None,
         }
     }
 }
 
-impl TUI {
+impl Tui {
     fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
+            self.poll_entity_query();
+            self.poll_gmsh();
+
             //calling to draw
             terminal.draw(|frame| {
                 frame.render_widget(&mut *self, frame.area());
@@ -125,10 +422,11 @@ impl TUI {
                 }
             })?;
 
-            //use manipulation process
-            match crossterm::event::read()? {
-                crossterm::event::Event::Key(key_evt) => self.handle_key_event(key_evt)?,
-                _ => {}
+            //use manipulation process, wait shortly so the Gmsh status keeps updating
+            if crossterm::event::poll(TICK)?
+                && let crossterm::event::Event::Key(key_evt) = crossterm::event::read()?
+            {
+                self.handle_key_event(key_evt)?
             }
         }
 
@@ -136,6 +434,16 @@ impl TUI {
     }
 
     fn handle_key_event(&mut self, key_evt: crossterm::event::KeyEvent) -> io::Result<()> {
+        //the error popup takes all keys until it is dismissed
+        if self.error_popup.is_some() {
+            if let (KeyEventKind::Press, KeyCode::Esc | KeyCode::Enter) =
+                (key_evt.kind, key_evt.code)
+            {
+                self.error_popup = None;
+            }
+            return Ok(());
+        }
+
         match self.opreation_mode {
             OperaMode::Select => match (key_evt.kind, key_evt.code) {
                 (KeyEventKind::Press, KeyCode::Esc) => self.exit = true,
@@ -148,28 +456,51 @@ impl TUI {
                 (KeyEventKind::Press, KeyCode::Char('a') | KeyCode::Char('A'))
                     if !key_evt.modifiers.contains(KeyModifiers::CONTROL) =>
                 {
-                    if let Some(mut child) = self.gmsh_handle.take() {
-                        //kill the previous Gmsh Child Process first
-                        if let Err(e) = child.kill() {
-                            panic!("{}", e)
-                        }
-                    }
-                    if let Ok(gmsh_handle) = self.gmesh_para.apply_mesh().0 {
-                        self.gmsh_handle = Some(gmsh_handle);
-                    }
+                    self.restart_gmsh(ScriptAction::Preview)
                 }
                 (KeyEventKind::Press, KeyCode::Char('a') | KeyCode::Char('A'))
                     if key_evt.modifiers.contains(KeyModifiers::CONTROL) =>
                 {
-                    if let Some(mut child) = self.gmsh_handle.take() {
-                        //kill the previous Gmsh Child Process first
-                        if let Err(e) = child.kill() {
-                            panic!("{}", e)
-                        }
+                    self.restart_gmsh(ScriptAction::Export)
+                }
+
+                (KeyEventKind::Press, KeyCode::Char('s') | KeyCode::Char('S'))
+                    if key_evt.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    match save_project(&self.project_file, &self.gmesh_para) {
+                        Ok(()) => self.message = format!("Project saved to {}", self.project_file),
+                        Err(e) => self.error_popup = Some(e),
                     }
-                    if let Ok(gmsh_handle) = self.gmesh_para.apply_mesh_and_save_to_nas().0 {
-                        self.gmsh_handle = Some(gmsh_handle);
+                }
+
+                (KeyEventKind::Press, KeyCode::Char('p') | KeyCode::Char('P')) => {
+                    //Ctrl+P previews the script of Ctrl+A, P previews the script of A
+                    let action = if key_evt.modifiers.contains(KeyModifiers::CONTROL) {
+                        ScriptAction::Export
+                    } else {
+                        ScriptAction::Preview
+                    };
+                    self.preview_script = self.gmesh_para.build_script(action).render();
+                    if self.gmesh_para.dimension_pending() {
+                        self.preview_script.insert_str(
+                            0,
+                            "// dimension: detecting, meshed in 3D until the entities are queried\n",
+                        );
                     }
+                    self.preview_scroll = 0;
+                    self.opreation_mode = OperaMode::Preview;
+                }
+
+                (KeyEventKind::Press, KeyCode::Char('q') | KeyCode::Char('Q'))
+                    if self.entity_query.is_none() =>
+                {
+                    self.start_entity_query();
+                }
+
+                (KeyEventKind::Press, KeyCode::Char('c') | KeyCode::Char('C'))
+                    if matches!(self.gmsh_status, GmshStatus::Meshing { .. }) =>
+                {
+                    self.cancel_gmsh()
                 }
 
                 (KeyEventKind::Press, KeyCode::Backspace | KeyCode::Delete) => {
@@ -177,14 +508,31 @@ impl TUI {
                 }
                 _ => {}
             },
+            OperaMode::Preview => match (key_evt.kind, key_evt.code) {
+                (KeyEventKind::Press, KeyCode::Esc | KeyCode::Enter) => {
+                    self.opreation_mode = OperaMode::Select;
+                }
+                (KeyEventKind::Press, KeyCode::Down) => {
+                    let line_count = self.preview_script.lines().count() as u16;
+                    if self.preview_scroll + 1 < line_count {
+                        self.preview_scroll += 1;
+                    }
+                }
+                (KeyEventKind::Press, KeyCode::Up) => {
+                    self.preview_scroll = self.preview_scroll.saturating_sub(1);
+                }
+                _ => {}
+            },
             OperaMode::Modify => match (key_evt.kind, key_evt.code) {
                 (KeyEventKind::Press, KeyCode::Esc) => {
                     self.opreation_mode = OperaMode::Select;
-                    self.cursor.modify_type = ModifyType::None;
+                    self.input_error = None;
                 }
                 (KeyEventKind::Press, KeyCode::Enter) => self.confirm_modification(),
 
                 (KeyEventKind::Press, KeyCode::Tab) => self.modify_tab(),
+                (KeyEventKind::Press, KeyCode::Up) => self.pick_choice(true),
+                (KeyEventKind::Press, KeyCode::Down) => self.pick_choice(false),
                 (KeyEventKind::Press, KeyCode::Char(ch)) => self.char_insert(ch),
                 (KeyEventKind::Press, KeyCode::Backspace) => self.char_backspace(), //delete the char before cursor
                 (KeyEventKind::Press, KeyCode::Delete) => self.char_delete(), //delete the char selected by the cursor
@@ -198,259 +546,694 @@ impl TUI {
         Ok(())
     }
 
-    ///////////////////////////////////// Select Mode
-    fn table_state_down(&mut self) {
-        if let Some(idx) = self.table_state.selected() {
-            match self.cur_type {
-                TypeMode::Volume => {
-                    if idx < self.gmesh_para.vol_phy_list.len() {
-                        self.table_state.select(Some(idx + 1));
-                    } else {
-                        self.table_state.select(None);
-                    }
-                }
-                TypeMode::Surface => {
-                    if idx < self.gmesh_para.surf_phy_list.len() {
-                        self.table_state.select(Some(idx + 1));
-                    } else {
-                        self.table_state.select(None);
-                    }
-                }
-                TypeMode::Mesh => {
-                    //if more parameters exist, this need change
-                    if idx == 0 {
-                        self.table_state.select(Some(0));
-                    } else {
-                        self.table_state.select(None);
-                    }
-                }
-                _ => {}
+    fn restart_gmsh(&mut self, action: ScriptAction) {
+        //an automatic dimension is only known from the entities
+        if self.gmesh_para.dimension_pending() && self.entity_query.is_some() {
+            self.gmsh_pending = Some(action);
+            self.message = String::from("Dimension: detecting, Gmsh starts after the entity query");
+            return;
+        }
+
+        //Gmsh would only warn and mesh without the broken field
+        if let Err(e) = self
+            .gmesh_para
+            .check_fields(self.entity_list.as_ref())
+            .and_then(|_| self.gmesh_para.check_advanced_options())
+            .and_then(|_| self.gmesh_para.check_boundary_layer())
+            .and_then(|_| match action {
+                //the same checks as the batch export
+                ScriptAction::Export => self
+                    .gmesh_para
+                    .check_conditions()
+                    .and_then(|_| self.gmesh_para.check_decks(&self.material_library)),
+                _ => Ok(()),
+            })
+        {
+            self.error_popup = Some(e);
+            return;
+        }
+
+        //kill the previous Gmsh Child Process first
+        if let Err(e) = self.stop_gmsh() {
+            self.error_popup = Some(e);
+            return;
+        }
+
+        let gmsh_handle_result = match action {
+            ScriptAction::Export => self.gmesh_para.apply_mesh_and_export(),
+            _ => self.gmesh_para.apply_mesh(),
+        };
+        match gmsh_handle_result {
+            Ok(gmsh_handle) => self.watch_gmsh(gmsh_handle, action),
+            Err(e) => {
+                self.gmsh_status = GmshStatus::Idle;
+                self.error_popup = Some(e);
             }
-        } else {
-            match self.cur_type {
-                TypeMode::Volume => {
-                    self.table_state.select(Some(0));
-                }
-                TypeMode::Surface => {
-                    self.table_state.select(Some(0));
-                }
-                TypeMode::Mesh => {
-                    self.table_state.select(Some(0));
-                }
-                TypeMode::None => {
-                    self.cur_type = TypeMode::Volume;
-                    self.table_state.select(Some(0));
-                }
+        }
+    }
+
+    fn stop_gmsh(&mut self) -> Result<()> {
+        if let Some(mut child) = self.gmsh_handle.take() {
+            child
+                .kill()
+                .and_then(|_| child.wait())
+                .map_err(TermshError::io("failed to stop Gmsh"))?;
+        }
+        Ok(())
+    }
+
+    fn watch_gmsh(&mut self, mut gmsh_handle: Child, action: ScriptAction) {
+        self.gmsh_log = GmshLog::capture(&mut gmsh_handle);
+        self.gmsh_handle = Some(gmsh_handle);
+        self.gmsh_done_markers = self.gmesh_para.done_markers(action);
+        self.gmsh_exported = match action {
+            ScriptAction::Export => Some(self.gmesh_para.clone()),
+            _ => None,
+        };
+        self.gmsh_status = GmshStatus::Meshing {
+            since: Instant::now(),
+        };
+    }
+
+    fn cancel_gmsh(&mut self) {
+        match self.stop_gmsh() {
+            Ok(()) => {
+                self.message = String::from("Gmsh cancelled");
+                self.gmsh_status = GmshStatus::Cancelled;
             }
+            Err(e) => self.error_popup = Some(e),
         }
     }
 
-    fn table_state_up(&mut self) {
-        if let Some(idx) = self.table_state.selected() {
-            match self.cur_type {
-                TypeMode::Volume => {
-                    if idx > 0 {
-                        self.table_state.select(Some(idx - 1));
-                    } else {
-                        self.table_state.select(None);
-                    }
-                }
-                TypeMode::Surface => {
-                    if idx > 0 {
-                        self.table_state.select(Some(idx - 1));
-                    } else {
-                        self.table_state.select(None);
-                    }
-                }
-                TypeMode::Mesh => {
-                    if idx > 0 {
-                        self.table_state.select(Some(idx - 1));
-                    } else {
-                        self.table_state.select(None);
+    //the GUI stays open after the script, so "done" is read from the log, not from the exit
+    fn poll_gmsh(&mut self) {
+        let Some(child) = &mut self.gmsh_handle else {
+            return;
+        };
+
+        match child.try_wait() {
+            Ok(None) => {
+                if let GmshStatus::Meshing { since } = self.gmsh_status {
+                    if let Some(failure) = self.gmsh_log.failure() {
+                        self.gmsh_status = GmshStatus::Failed { reason: failure };
+                    } else if self
+                        .gmsh_done_markers
+                        .iter()
+                        .all(|marker| self.gmsh_log.contains(marker))
+                    {
+                        self.gmsh_status = GmshStatus::Done {
+                            took: since.elapsed(),
+                        };
+                        self.finish_export();
                     }
                 }
-                _ => {}
             }
-        } else {
-            match self.cur_type {
-                TypeMode::Volume => {
-                    self.table_state
-                        .select(Some(self.gmesh_para.vol_phy_list.len()));
-                }
-                TypeMode::Surface => {
-                    self.table_state
-                        .select(Some(self.gmesh_para.surf_phy_list.len()));
-                }
-                TypeMode::Mesh => {
-                    //this need change when more parameters exist
-                    self.table_state.select(Some(0));
-                }
-                TypeMode::None => {
-                    self.cur_type = TypeMode::Volume;
-                    self.table_state
-                        .select(Some(self.gmesh_para.vol_phy_list.len()));
+            Ok(Some(exit_status)) => {
+                self.gmsh_handle = None;
+                if !exit_status.success() {
+                    let reason = match exit_status.code() {
+                        Some(code) => format!("exit code {}", code),
+                        None => String::from("killed by a signal"),
+                    };
+                    self.gmsh_status = GmshStatus::Failed { reason };
+                } else if let Some(failure) = self.gmsh_log.failure() {
+                    self.gmsh_status = GmshStatus::Failed { reason: failure };
+                } else {
+                    self.gmsh_status = GmshStatus::WindowClosed;
                 }
             }
+            Err(e) => {
+                self.gmsh_handle = None;
+                self.gmsh_status = GmshStatus::Failed {
+                    reason: e.to_string(),
+                };
+            }
         }
     }
 
-    fn type_mode_left(&mut self) {
-        match self.cur_type {
-            TypeMode::Volume => {
-                self.cur_type = TypeMode::None;
-                self.table_state.select(None);
-            }
-            TypeMode::Surface => {
-                self.cur_type = TypeMode::Volume;
-                if let Some(idx) = self.table_state.selected()
-                    && idx > self.gmesh_para.vol_phy_list.len()
-                {
-                    self.table_state
-                        .select(Some(self.gmesh_para.vol_phy_list.len()));
-                }
-                if self.table_state.selected().is_none() {
-                    self.table_state.select(Some(0));
+    //complete the exported Nastran files into decks, with the settings they were meshed with
+    fn finish_export(&mut self) {
+        let Some(gmsh_para) = self.gmsh_exported.take() else {
+            return;
+        };
+        match write_decks(&gmsh_para, &self.material_library) {
+            Ok(warnings) => {
+                if let Some(warning) = warnings.first() {
+                    self.message = format!("{} warning(s), first: {}", warnings.len(), warning);
                 }
             }
-            TypeMode::Mesh => {
-                self.cur_type = TypeMode::Surface;
-                if let Some(idx) = self.table_state.selected()
-                    && idx > self.gmesh_para.surf_phy_list.len()
-                {
-                    self.table_state
-                        .select(Some(self.gmesh_para.surf_phy_list.len()));
+            Err(e) => self.error_popup = Some(e),
+        }
+    }
+
+    fn start_entity_query(&mut self) {
+        let gmsh_para = self.gmesh_para.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            //the receiver is gone when termsh quits first, nothing to do then
+            let _ = sender.send(gmsh_para.query_entities());
+        });
+
+        //the old list no longer matches the model, coverage and fields wait for the new one
+        self.entity_list = None;
+        self.gmesh_para.detected_dimension = None;
+        self.entity_query = Some(receiver);
+        self.entity_error = None;
+    }
+
+    fn poll_entity_query(&mut self) {
+        if let Some(receiver) = &self.entity_query {
+            match receiver.try_recv() {
+                Ok(Ok(entity_list)) => {
+                    self.gmesh_para.detected_dimension = Some(entity_list.dimension());
+                    self.entity_list = Some(entity_list);
+                    self.entity_query = None;
                 }
-                if self.table_state.selected().is_none() {
-                    self.table_state.select(Some(0));
+                Ok(Err(e)) => {
+                    self.entity_error = Some(e.to_string());
+                    self.entity_query = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.entity_error = Some(String::from("entity query stopped unexpectedly"));
+                    self.entity_query = None;
                 }
             }
-            TypeMode::None => {
-                self.cur_type = TypeMode::Mesh;
-                self.table_state.select(Some(0));
-            }
+        }
+
+        //after a failed query it falls back to 3D, Gmsh shows what is wrong with the model
+        if self.entity_query.is_none()
+            && let Some(action) = self.gmsh_pending.take()
+        {
+            self.restart_gmsh(action);
         }
     }
 
-    fn type_mode_right(&mut self) {
+    ///////////////////////////////////// Select Mode
+    //number of selectable rows of current table, including the "Enter To Add" row
+    fn row_count(&self) -> usize {
         match self.cur_type {
-            TypeMode::Volume => {
-                self.cur_type = TypeMode::Surface;
-                if let Some(idx) = self.table_state.selected()
-                    && idx > self.gmesh_para.surf_phy_list.len()
-                {
-                    self.table_state
-                        .select(Some(self.gmesh_para.surf_phy_list.len()));
-                }
-                if self.table_state.selected().is_none() {
-                    self.table_state.select(Some(0));
-                }
-            }
-            TypeMode::Surface => {
-                self.cur_type = TypeMode::Mesh;
-                self.table_state.select(Some(0));
-            }
-            TypeMode::Mesh => {
-                self.cur_type = TypeMode::None;
-                self.table_state.select(None);
-            }
-            TypeMode::None => {
-                self.cur_type = TypeMode::Volume;
-                self.table_state.select(Some(0));
-            }
+            TypeMode::Volume => self.gmesh_para.vol_phy_list.len() + 1,
+            TypeMode::Surface => self.gmesh_para.surf_phy_list.len() + 1,
+            TypeMode::Curve => self.gmesh_para.curve_phy_list.len() + 1,
+            TypeMode::Point => self.gmesh_para.point_phy_list.len() + 1,
+            TypeMode::Entity => self
+                .entity_list
+                .as_ref()
+                .map_or(0, |entity_list| entity_list.entities.len()),
+            TypeMode::Part => self.gmesh_para.geometry_parts.len() + 1,
+            TypeMode::Geometry => GEOMETRY_OPTIONS.len(),
+            TypeMode::Mesh => MESH_OPTIONS.len(),
+            TypeMode::Advanced => self.gmesh_para.advanced_options.len() + 1,
+            TypeMode::Field => self.gmesh_para.fields.len() + 1,
+            TypeMode::Layer => LAYER_OPTIONS.len(),
+            TypeMode::Condition => condition_rows(&self.gmesh_para).len() + 1,
+            TypeMode::Export => self.gmesh_para.export_settings.len(),
+            TypeMode::Log => self.gmsh_log.lines().len(),
+            TypeMode::None => 0,
         }
     }
 
-    fn select_to_modify(&mut self) {
-        if let Some(idx) = self.table_state.selected() {
-            self.opreation_mode = OperaMode::Modify;
-            //setting up input buffer
-            match self.cur_type {
-                TypeMode::Volume => {
-                    self.input_buf.clear();
-                    if idx < self.gmesh_para.vol_phy_list.len() {
-                        //exsiting parameters selected
-                        let temp_vol_phy = self.gmesh_para.vol_phy_list[idx].clone();
+    fn table_state_down(&mut self) {
+        if let TypeMode::None = self.cur_type {
+            self.cur_type = TypeMode::Volume;
+            self.table_state.select(Some(0));
+            return;
+        }
 
-                        self.input_buf.push(temp_vol_phy.name);
-                        self.input_buf.push(temp_vol_phy.phys_id);
-                        self.input_buf.push(temp_vol_phy.vol_ids);
+        match self.table_state.selected() {
+            Some(idx) if idx + 1 < self.row_count() => self.table_state.select(Some(idx + 1)),
+            Some(_) => self.table_state.select(None),
+            None => self.table_state.select(Some(0)),
+        }
+    }
 
-                        //set cursor
-                        self.cursor.modify_type = ModifyType::VolName;
-                    } else {
-                        //adding parameters
-                        self.input_buf.push(String::new());
-                        self.input_buf.push(String::new());
-                        self.input_buf.push(String::new());
+    fn table_state_up(&mut self) {
+        if let TypeMode::None = self.cur_type {
+            self.cur_type = TypeMode::Volume;
+        }
 
-                        //set cursor
-                        self.cursor.modify_type = ModifyType::VolName;
-                    }
+        match self.table_state.selected() {
+            Some(idx) if idx > 0 => self.table_state.select(Some(idx - 1)),
+            Some(_) => self.table_state.select(None),
+            None => self
+                .table_state
+                .select(Some(self.row_count().saturating_sub(1))),
+        }
+    }
 
-                    self.cursor.char_idx = self.input_buf[0].len() as u16;
-                }
+    fn type_mode_left(&mut self) {
+        self.cur_type = self.cur_type.left();
+        self.clamp_selection();
+    }
 
-                TypeMode::Surface => {
-                    self.input_buf.clear();
-                    if idx < self.gmesh_para.surf_phy_list.len() {
-                        //exsiting parameters selected
-                        let temp_surf_phy = self.gmesh_para.surf_phy_list[idx].clone();
+    fn type_mode_right(&mut self) {
+        self.cur_type = self.cur_type.right();
+        self.clamp_selection();
+    }
 
-                        self.input_buf.push(temp_surf_phy.name);
-                        self.input_buf.push(temp_surf_phy.phys_id);
-                        self.input_buf.push(temp_surf_phy.surf_ids);
+    //keep the shared selection inside the rows of the newly selected table
+    fn clamp_selection(&mut self) {
+        if let TypeMode::None = self.cur_type {
+            self.table_state.select(None);
+            return;
+        }
 
-                        //set cursor
-                        self.cursor.modify_type = ModifyType::SurName;
-                    } else {
-                        //adding parameters
-                        self.input_buf.push(String::new());
-                        self.input_buf.push(String::new());
-                        self.input_buf.push(String::new());
+        let last_idx = self.row_count().saturating_sub(1);
+        match self.table_state.selected() {
+            Some(idx) if idx > last_idx => self.table_state.select(Some(last_idx)),
+            Some(_) => {}
+            None => self.table_state.select(Some(0)),
+        }
+    }
 
-                        //set cursor
-                        self.cursor.modify_type = ModifyType::SurName;
-                    }
-                    self.cursor.char_idx = self.input_buf[0].len() as u16;
+    fn select_to_modify(&mut self) {
+        if let Some(idx) = self.table_state.selected() {
+            //setting up input buffer
+            self.input_buf = match self.cur_type {
+                TypeMode::Volume => {
+                    //exsiting parameters selected, or adding parameters
+                    let temp_vol_phy = self.gmesh_para.vol_phy_list.get(idx).cloned();
+                    let (name, phys_id, vol_ids, mesh_size, material) = match temp_vol_phy {
+                        Some(vol_phy) => (
+                            vol_phy.name,
+                            vol_phy.phys_id,
+                            vol_phy.vol_ids.to_string(),
+                            vol_phy.mesh_size,
+                            vol_phy.material,
+                        ),
+                        None => (
+                            String::new(),
+                            String::new(),
+                            String::new(),
+                            String::new(),
+                            String::new(),
+                        ),
+                    };
+                    vec![
+                        InputField::new("Name", name, any_char),
+                        InputField::new("Physical ID", phys_id, digit_char),
+                        InputField::new("Volume ID", vol_ids, id_list_char).fill(),
+                        InputField::new("Mesh Size", mesh_size, float_char)
+                            .width(Constraint::Length(12)),
+                        InputField::new("Material (↑↓ to pick)", material, any_char)
+                            .choices(material_choices(&self.material_library))
+                            .width(Constraint::Length(24)),
+                    ]
+                }
+                TypeMode::Surface => {
+                    //exsiting parameters selected, or adding parameters
+                    let temp_surf_phy = self.gmesh_para.surf_phy_list.get(idx).cloned();
+                    let surf_phy = temp_surf_phy.unwrap_or_else(|| SurfPhys {
+                        name: String::new(),
+                        phys_id: String::new(),
+                        surf_ids: IdSet::new(),
+                        mesh_size: String::new(),
+                        boundary_conditions: Vec::new(),
+                        material: String::new(),
+                        thickness: String::new(),
+                    });
+
+                    vec![
+                        InputField::new("Name", surf_phy.name, any_char),
+                        InputField::new("Physical ID", surf_phy.phys_id, digit_char),
+                        InputField::new("Surface ID", surf_phy.surf_ids.to_string(), id_list_char)
+                            .fill(),
+                        InputField::new("Mesh Size", surf_phy.mesh_size, float_char)
+                            .width(Constraint::Length(12)),
+                        //shell properties, only used by 2D models
+                        InputField::new("Shell Material (↑↓)", surf_phy.material, any_char)
+                            .choices(material_choices(&self.material_library))
+                            .width(Constraint::Length(24)),
+                        InputField::new("Thickness", surf_phy.thickness, float_char)
+                            .width(Constraint::Length(12)),
+                    ]
+                }
+                TypeMode::Curve => {
+                    //exsiting parameters selected, or adding parameters
+                    let temp_curve_phy = self.gmesh_para.curve_phy_list.get(idx).cloned();
+                    let (name, phys_id, curve_ids, boundary_layer) = match temp_curve_phy {
+                        Some(curve_phy) => (
+                            curve_phy.name,
+                            curve_phy.phys_id,
+                            curve_phy.curve_ids.to_string(),
+                            curve_phy.boundary_layer,
+                        ),
+                        None => (String::new(), String::new(), String::new(), false),
+                    };
+
+                    vec![
+                        InputField::new("Name", name, any_char),
+                        InputField::new("Physical ID", phys_id, digit_char),
+                        InputField::new("Curve ID", curve_ids, id_list_char).fill(),
+                        InputField::new("BL Wall (y/n)", yes_no(boundary_layer), yes_no_char)
+                            .width(Constraint::Length(15)),
+                    ]
+                }
+                TypeMode::Point => {
+                    //exsiting parameters selected, or adding parameters
+                    let temp_point_phy = self.gmesh_para.point_phy_list.get(idx).cloned();
+                    let (name, phys_id, point_ids) = match temp_point_phy {
+                        Some(point_phy) => (
+                            point_phy.name,
+                            point_phy.phys_id,
+                            point_phy.point_ids.to_string(),
+                        ),
+                        None => (String::new(), String::new(), String::new()),
+                    };
+
+                    vec![
+                        InputField::new("Name", name, any_char),
+                        InputField::new("Physical ID", phys_id, digit_char),
+                        InputField::new("Point ID", point_ids, id_list_char).fill(),
+                    ]
+                }
+                TypeMode::Part => {
+                    //exsiting part selected, or adding a part
+                    let part = self
+                        .gmesh_para
+                        .geometry_parts
+                        .get(idx)
+                        .cloned()
+                        .unwrap_or_else(|| GeometryPart::new(String::new()));
+                    let [x, y, z] = part.translation;
+
+                    vec![
+                        InputField::new("File", part.file, any_char).fill(),
+                        InputField::new("Scale", part.scale.to_string(), number_char)
+                            .width(Constraint::Length(10)),
+                        InputField::new("Move X", x.to_string(), signed_number_char)
+                            .width(Constraint::Length(10)),
+                        InputField::new("Move Y", y.to_string(), signed_number_char)
+                            .width(Constraint::Length(10)),
+                        InputField::new("Move Z", z.to_string(), signed_number_char)
+                            .width(Constraint::Length(10)),
+                    ]
+                }
+                TypeMode::Geometry if idx == DIMENSION_ROW => {
+                    let choices = ModelDimension::ALL
+                        .iter()
+                        .map(|dimension| (dimension.label(), dimension.description()))
+                        .collect::<Vec<_>>();
+
+                    vec![
+                        InputField::new("Name", GEOMETRY_OPTIONS[idx].to_string(), any_char)
+                            .read_only(),
+                        InputField::new(
+                            "Value (↑↓ to pick)",
+                            geometry_value(&self.gmesh_para, idx),
+                            any_char,
+                        )
+                        .choices(to_dropdown(&choices))
+                        .fill(),
+                    ]
+                }
+                TypeMode::Geometry => {
+                    let (title, accept): (&'static str, fn(char) -> bool) = match idx {
+                        TOLERANCE_ROW => ("Value (empty for default)", float_char),
+                        SOURCE_UNIT_ROW | TARGET_UNIT_ROW => {
+                            ("Value (m/cm/mm/um/in/ft)", unit_char)
+                        }
+                        _ => ("Value (y/n)", yes_no_char),
+                    };
+
+                    vec![
+                        InputField::new("Name", GEOMETRY_OPTIONS[idx].to_string(), any_char)
+                            .read_only(),
+                        InputField::new(title, geometry_value(&self.gmesh_para, idx), accept)
+                            .fill(),
+                    ]
                 }
                 TypeMode::Mesh => {
-                    self.input_buf.clear();
-                    match idx {
-                        0 => {
-                            self.input_buf.push(String::from("MaxSize"));
-                            self.input_buf
-                                .push(self.gmesh_para.mesh_paras.max_size.clone());
-
-                            //set cursor
-                            self.cursor.modify_type = ModifyType::MeshVal;
-                            self.cursor.char_idx = self.input_buf[1].len() as u16;
+                    let option = &MESH_OPTIONS[idx];
+                    let value = self.gmesh_para.mesh_paras.get(option.name).to_string();
+                    let value_field = match option.kind {
+                        OptionKind::Length | OptionKind::Number => {
+                            InputField::new("Value (empty for default)", value, float_char)
                         }
-                        _ => {
-                            self.input_buf.push(String::new());
-                            self.input_buf.push(String::new());
-
-                            //set cursor
-                            self.cursor.modify_type = ModifyType::MeshVal;
-                            self.cursor.char_idx = 0;
+                        OptionKind::Integer { .. } => {
+                            InputField::new("Value (empty for default)", value, digit_char)
+                        }
+                        OptionKind::Bool | OptionKind::Choice(_) => {
+                            let mut choices = vec![("", "Gmsh default")];
+                            choices.extend_from_slice(option.choices());
+                            InputField::new("Value (↑↓ to pick)", value, any_char)
+                                .choices(to_dropdown(&choices))
+                        }
+                    };
+
+                    vec![
+                        InputField::new(
+                            "Name",
+                            mesh_option_label(option, self.gmesh_para.units.target),
+                            any_char,
+                        )
+                        .read_only(),
+                        value_field.fill(),
+                    ]
+                }
+                TypeMode::Advanced => {
+                    //exsiting option selected, or adding an option
+                    let (name, value, unchecked) = match self.gmesh_para.advanced_options.get(idx) {
+                        Some(option) => {
+                            (option.name.clone(), option.value.clone(), option.unchecked)
+                        }
+                        None => (String::new(), String::new(), false),
+                    };
+
+                    vec![
+                        InputField::new("Name (↑↓ Tab to complete)", name, any_char)
+                            .suggest(option_suggestions)
+                            .fill(),
+                        InputField::new("Value", value, any_char).width(Constraint::Length(24)),
+                        InputField::new("Unchecked", yes_no(unchecked), yes_no_char)
+                            .width(Constraint::Length(11)),
+                    ]
+                }
+                TypeMode::Field => match self.gmesh_para.fields.get(idx) {
+                    Some(field) => {
+                        let is_background = self.gmesh_para.background_field == Some(field.id);
+                        let mut input_fields = vec![
+                            InputField::new("ID", field.id.to_string(), any_char)
+                                .read_only()
+                                .width(Constraint::Length(6)),
+                            InputField::new("Type", field.kind.to_string(), any_char)
+                                .read_only()
+                                .width(Constraint::Length(11)),
+                            InputField::new("Background", yes_no(is_background), yes_no_char)
+                                .width(Constraint::Length(12)),
+                        ];
+                        for param in field.kind.params() {
+                            input_fields.push(field_param_input(param, field.get(param.name)));
+                        }
+                        input_fields
+                    }
+                    //a new field, the parameters follow from the type
+                    None => {
+                        let kinds = FieldKind::ALL
+                            .iter()
+                            .map(|kind| (kind.label(), kind.description()))
+                            .collect::<Vec<_>>();
+                        vec![
+                            InputField::new(
+                                "Type (↑↓ to pick)",
+                                FieldKind::Distance.to_string(),
+                                any_char,
+                            )
+                            .choices(to_dropdown(&kinds))
+                            .fill(),
+                        ]
+                    }
+                },
+                TypeMode::Condition => match condition_rows(&self.gmesh_para).get(idx) {
+                    Some(&(dim, group_idx, condition_idx)) => {
+                        let name = condition_group_name(&self.gmesh_para, dim, group_idx);
+                        let condition = self.gmesh_para.group_conditions(dim, group_idx).unwrap()
+                            [condition_idx]
+                            .clone();
+                        let unit = self.gmesh_para.units.target;
+
+                        let mut input_fields = vec![
+                            InputField::new("Group", group_label(dim, name), any_char)
+                                .read_only()
+                                .width(Constraint::Length(20)),
+                            InputField::new("Type", condition.kind.to_string(), any_char)
+                                .read_only()
+                                .width(Constraint::Length(13)),
+                        ];
+                        for param in condition.kind.params() {
+                            //a bare number is in the unit system of the model
+                            let title =
+                                format!("{} ({})", param.name, param.quantity.model_unit(unit));
+                            input_fields.push(
+                                InputField::new(
+                                    title,
+                                    condition.get(param.name).to_string(),
+                                    any_char,
+                                )
+                                .fill(),
+                            );
                         }
+                        input_fields
+                    }
+                    //a new condition, its values follow from the type
+                    None => {
+                        let groups = condition_group_choices(&self.gmesh_para);
+                        let Some((first_group, _)) = groups.first().cloned() else {
+                            self.message = String::from(
+                                "Boundary conditions need a surface, curve or point group first",
+                            );
+                            return;
+                        };
+                        let kinds = BcKind::ALL
+                            .iter()
+                            .map(|kind| (kind.label(), kind.description()))
+                            .collect::<Vec<_>>();
+                        vec![
+                            InputField::new("Group (↑↓ to pick)", first_group, any_char)
+                                .choices(groups)
+                                .fill(),
+                            InputField::new(
+                                "Type (↑↓ to pick)",
+                                BcKind::Fixed.to_string(),
+                                any_char,
+                            )
+                            .choices(to_dropdown(&kinds))
+                            .fill(),
+                        ]
                     }
+                },
+                TypeMode::Layer => {
+                    let (title, accept): (&'static str, fn(char) -> bool) = match idx {
+                        WALL_CURVES_ROW => ("Value (eg. 1,3:5)", id_list_char),
+                        1 => ("Value (first layer, next to the wall)", float_char),
+                        2 => ("Value (eg. 1.2)", number_char),
+                        _ => ("Value", digit_char),
+                    };
+
+                    vec![
+                        InputField::new("Name", LAYER_OPTIONS[idx].to_string(), any_char)
+                            .read_only(),
+                        InputField::new(
+                            title,
+                            layer_value(&self.gmesh_para.boundary_layer, idx),
+                            accept,
+                        )
+                        .fill(),
+                    ]
                 }
-                _ => {}
-            }
+                TypeMode::Export => {
+                    let setting = &self.gmesh_para.export_settings[idx];
+
+                    let mut fields = vec![
+                        InputField::new("Format", setting.format.label().to_string(), any_char)
+                            .read_only(),
+                        InputField::new("Export (y/n)", yes_no(setting.enabled), yes_no_char),
+                        InputField::new("Save All (y/n)", yes_no(setting.save_all), yes_no_char),
+                        InputField::new(
+                            "Tag Type (1 elem/2 phys/3 part)",
+                            setting.element_tag_type.to_string(),
+                            digit_char,
+                        )
+                        .fill(),
+                    ];
+                    //only Nastran files are completed into decks
+                    let deck =
+                        InputField::new("Deck (y/n)", yes_no(setting.nastran_deck), yes_no_char);
+                    fields.push(if setting.format.is_nastran() {
+                        deck
+                    } else {
+                        deck.read_only()
+                    });
+                    fields
+                }
+                TypeMode::Entity => {
+                    let Some(entity) = self
+                        .entity_list
+                        .as_ref()
+                        .and_then(|entity_list| entity_list.entities.get(idx))
+                    else {
+                        return;
+                    };
+                    let kind = if entity.dim == 3 { "Volume" } else { "Surface" };
+                    let mesh_size = self.gmesh_para.entity_mesh_size(entity.dim, entity.tag);
+                    let (nodes, recombine) = self
+                        .gmesh_para
+                        .structured_entity(entity.dim, entity.tag)
+                        .map_or((String::new(), false), |structured| {
+                            (structured.nodes.clone(), structured.recombine)
+                        });
+
+                    vec![
+                        InputField::new("Entity", format!("{} {}", kind, entity.tag), any_char)
+                            .read_only(),
+                        InputField::new(
+                            "Mesh Size (empty for group size)",
+                            mesh_size.to_string(),
+                            float_char,
+                        )
+                        .fill(),
+                        InputField::new("Transfinite Nodes", nodes, digit_char)
+                            .width(Constraint::Length(19)),
+                        InputField::new("Recombine (y/n)", yes_no(recombine), yes_no_char)
+                            .width(Constraint::Length(17)),
+                    ]
+                }
+                TypeMode::Log | TypeMode::None => return, //read only tables
+            };
+
+            //set cursor at the end of the first editable field
+            self.cursor.field_idx = self
+                .input_buf
+                .iter()
+                .position(|field| field.editable)
+                .unwrap_or(0);
+            self.cursor.char_idx =
+                self.input_buf[self.cursor.field_idx].value.chars().count() as u16;
+
+            self.opreation_mode = OperaMode::Modify;
         }
     }
 
     fn delete_selected(&mut self) {
         if let Some(select_idx) = self.table_state.selected() {
             match self.cur_type {
-                TypeMode::Volume => {
-                    if select_idx < self.gmesh_para.vol_phy_list.len() {
-                        self.gmesh_para.vol_phy_list.remove(select_idx);
+                TypeMode::Volume if select_idx < self.gmesh_para.vol_phy_list.len() => {
+                    self.gmesh_para.vol_phy_list.remove(select_idx);
+                }
+                TypeMode::Surface if select_idx < self.gmesh_para.surf_phy_list.len() => {
+                    self.gmesh_para.surf_phy_list.remove(select_idx);
+                }
+                TypeMode::Curve if select_idx < self.gmesh_para.curve_phy_list.len() => {
+                    self.gmesh_para.curve_phy_list.remove(select_idx);
+                }
+                TypeMode::Point if select_idx < self.gmesh_para.point_phy_list.len() => {
+                    self.gmesh_para.point_phy_list.remove(select_idx);
+                }
+                TypeMode::Field if select_idx < self.gmesh_para.fields.len() => {
+                    let field = self.gmesh_para.fields.remove(select_idx);
+                    if self.gmesh_para.background_field == Some(field.id) {
+                        self.gmesh_para.background_field = None;
                     }
                 }
-                TypeMode::Surface => {
-                    if select_idx < self.gmesh_para.surf_phy_list.len() {
-                        self.gmesh_para.surf_phy_list.remove(select_idx);
+                TypeMode::Condition => {
+                    if let Some(&(dim, group_idx, condition_idx)) =
+                        condition_rows(&self.gmesh_para).get(select_idx)
+                        && let Some(conditions) =
+                            self.gmesh_para.group_conditions_mut(dim, group_idx)
+                    {
+                        conditions.remove(condition_idx);
+                    }
+                }
+                TypeMode::Advanced if select_idx < self.gmesh_para.advanced_options.len() => {
+                    self.gmesh_para.advanced_options.remove(select_idx);
+                }
+                TypeMode::Part if select_idx < self.gmesh_para.geometry_parts.len() => {
+                    if self.gmesh_para.geometry_parts.len() == 1 {
+                        self.message = String::from("The model needs at least one geometry file");
+                    } else {
+                        self.gmesh_para.geometry_parts.remove(select_idx);
+                        self.start_entity_query(); //volumes are renumbered
                     }
                 }
                 _ => {}
@@ -459,337 +1242,757 @@ impl TUI {
     }
 
     /////////////////////////// Modify Mode
-    fn modify_tab(&mut self) {
-        match self.cursor.modify_type {
-            ModifyType::VolName => {
-                self.cursor.modify_type = ModifyType::VolPID;
-                self.cursor.char_idx = self.input_buf[1].len() as u16;
-            }
-            ModifyType::VolPID => {
-                self.cursor.modify_type = ModifyType::VolVID;
-                self.cursor.char_idx = self.input_buf[2].len() as u16;
-            }
-            ModifyType::VolVID => {
-                self.cursor.modify_type = ModifyType::VolName;
-                self.cursor.char_idx = self.input_buf[0].len() as u16;
-            }
-            ModifyType::SurName => {
-                self.cursor.modify_type = ModifyType::SurPID;
-                self.cursor.char_idx = self.input_buf[1].len() as u16;
-            }
-            ModifyType::SurPID => {
-                self.cursor.modify_type = ModifyType::SurSID;
-                self.cursor.char_idx = self.input_buf[2].len() as u16;
-            }
-            ModifyType::SurSID => {
-                self.cursor.modify_type = ModifyType::SurName;
-                self.cursor.char_idx = self.input_buf[0].len() as u16;
+    //index of the next (or previous) editable field, wrapping around
+    fn next_field(&self, backward: bool) -> usize {
+        let field_count = self.input_buf.len();
+        let mut idx = self.cursor.field_idx;
+        for _ in 0..field_count {
+            idx = if backward {
+                (idx + field_count - 1) % field_count
+            } else {
+                (idx + 1) % field_count
+            };
+            if self.input_buf[idx].editable {
+                return idx;
             }
+        }
+        self.cursor.field_idx
+    }
 
-            _ => {}
+    fn modify_tab(&mut self) {
+        //take the chosen completion instead of moving on
+        let field = &mut self.input_buf[self.cursor.field_idx];
+        if let Some(idx) = field.suggestion_idx.take() {
+            let (suggestions, _) = field.dropdown();
+            if let Some((value, _)) = suggestions.get(idx) {
+                field.value = value.to_string();
+                self.cursor.char_idx = field.value.chars().count() as u16;
+                return;
+            }
         }
+
+        self.cursor.field_idx = self.next_field(false);
+        self.cursor.char_idx = self.input_buf[self.cursor.field_idx].value.chars().count() as u16;
     }
 
     fn char_insert(&mut self, ch: char) {
-        match self.cursor.modify_type {
-            ModifyType::VolName | ModifyType::SurName => {
-                let char_idx = self.cursor.char_idx;
-                self.input_buf[0].insert(char_idx as usize, ch);
-                self.cursor.char_idx += 1;
-            }
-            ModifyType::VolPID | ModifyType::SurPID => {
-                if ch.is_ascii_digit() {
-                    let char_idx = self.cursor.char_idx;
-                    self.input_buf[1].insert(char_idx as usize, ch);
-                    self.cursor.char_idx += 1;
-                }
-            }
-            ModifyType::VolVID | ModifyType::SurSID => {
-                if ch.is_ascii_digit() || ch == ',' {
-                    let char_idx = self.cursor.char_idx;
-                    self.input_buf[2].insert(char_idx as usize, ch);
-                    self.cursor.char_idx += 1;
-                }
-            }
+        let field = &mut self.input_buf[self.cursor.field_idx];
+        if field.editable && (field.accept)(ch) {
+            let idx = byte_idx(&field.value, self.cursor.char_idx);
+            field.value.insert(idx, ch);
+            field.suggestion_idx = None;
+            self.cursor.char_idx += 1;
+        }
+    }
 
-            ModifyType::MeshVal => {
-                if ch.is_ascii_digit() || ch == '.' {
-                    let char_idx = self.cursor.char_idx;
-                    self.input_buf[1].insert(char_idx as usize, ch);
-                    self.cursor.char_idx += 1;
-                }
-            }
-            _ => {}
+    //step through the completions of the field
+    fn pick_suggestion(&mut self, backward: bool) {
+        let field = &mut self.input_buf[self.cursor.field_idx];
+        let (suggestions, idx) = field.dropdown();
+        if suggestions.is_empty() {
+            return;
         }
+
+        field.suggestion_idx = match (idx, backward) {
+            (Some(0), true) | (None, true) => Some(suggestions.len() - 1),
+            (Some(idx), true) => Some(idx - 1),
+            (Some(idx), false) if idx + 1 < suggestions.len() => Some(idx + 1),
+            (_, false) => Some(0),
+        };
     }
 
-    fn char_backspace(&mut self) {
-        match self.cursor.modify_type {
-            ModifyType::VolName | ModifyType::SurName => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx > 0 {
-                    self.input_buf[0].remove((char_idx - 1) as usize);
-                    self.cursor.char_idx -= 1;
-                }
-            }
-            ModifyType::VolPID | ModifyType::SurPID => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx > 0 {
-                    self.input_buf[1].remove((char_idx - 1) as usize);
-                    self.cursor.char_idx -= 1;
-                }
-            }
-            ModifyType::VolVID | ModifyType::SurSID => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx > 0 {
-                    self.input_buf[2].remove((char_idx - 1) as usize);
-                    self.cursor.char_idx -= 1;
-                }
-            }
+    //step through the choices of a picked field, wrapping around
+    fn pick_choice(&mut self, backward: bool) {
+        let field = &mut self.input_buf[self.cursor.field_idx];
+        let choice_count = field.choices.len();
+        if choice_count == 0 {
+            return self.pick_suggestion(backward);
+        }
 
-            ModifyType::MeshVal => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx > 0 {
-                    self.input_buf[1].remove((char_idx - 1) as usize);
-                    self.cursor.char_idx -= 1;
-                }
-            }
-            _ => {}
+        let idx = field
+            .choices
+            .iter()
+            .position(|(value, _)| *value == field.value);
+        let idx = match (idx, backward) {
+            (Some(idx), true) => (idx + choice_count - 1) % choice_count,
+            (Some(idx), false) => (idx + 1) % choice_count,
+            (None, _) => 0,
+        };
+        field.value = field.choices[idx].0.to_string();
+        self.cursor.char_idx = 0;
+    }
+
+    fn char_backspace(&mut self) {
+        if !self.input_buf[self.cursor.field_idx].choices.is_empty() {
+            return; //picked, not typed
+        }
+        let char_idx = self.cursor.char_idx;
+        if char_idx > 0 {
+            let field = &mut self.input_buf[self.cursor.field_idx];
+            let idx = byte_idx(&field.value, char_idx - 1);
+            field.value.remove(idx);
+            field.suggestion_idx = None;
+            self.cursor.char_idx -= 1;
         }
     }
 
     fn char_delete(&mut self) {
-        match self.cursor.modify_type {
-            ModifyType::VolName | ModifyType::SurName => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx < self.input_buf[0].len() as u16 && (!self.input_buf[0].is_empty()) {
-                    self.input_buf[0].remove(char_idx as usize);
-                }
-            }
-            ModifyType::VolPID | ModifyType::SurPID => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx < self.input_buf[1].len() as u16 && (!self.input_buf[1].is_empty()) {
-                    self.input_buf[1].remove(char_idx as usize);
-                }
-            }
-            ModifyType::VolVID | ModifyType::SurSID => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx < self.input_buf[2].len() as u16 && (!self.input_buf[2].is_empty()) {
-                    self.input_buf[2].remove(char_idx as usize);
-                }
-            }
-
-            ModifyType::MeshVal => {
-                let char_idx = self.cursor.char_idx;
-                if char_idx < self.input_buf[1].len() as u16 && (!self.input_buf[1].is_empty()) {
-                    self.input_buf[1].remove(char_idx as usize);
-                }
-            }
-            _ => {}
+        if !self.input_buf[self.cursor.field_idx].choices.is_empty() {
+            return; //picked, not typed
+        }
+        let char_idx = self.cursor.char_idx;
+        let field = &mut self.input_buf[self.cursor.field_idx];
+        if char_idx < field.value.chars().count() as u16 && (!field.value.is_empty()) {
+            let idx = byte_idx(&field.value, char_idx);
+            field.value.remove(idx);
+            field.suggestion_idx = None;
         }
     }
 
     fn cursor_left(&mut self) {
-        match self.cursor.modify_type {
-            ModifyType::VolName => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::VolVID;
-                    self.cursor.char_idx = self.input_buf[2].len() as u16;
-                }
-            }
-            ModifyType::VolPID => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::VolName;
-                    self.cursor.char_idx = self.input_buf[0].len() as u16;
-                }
-            }
-            ModifyType::VolVID => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::VolPID;
-                    self.cursor.char_idx = self.input_buf[1].len() as u16;
-                }
-            }
-            ModifyType::SurName => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::SurSID;
-                    self.cursor.char_idx = self.input_buf[2].len() as u16;
-                }
-            }
-            ModifyType::SurPID => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::SurName;
-                    self.cursor.char_idx = self.input_buf[0].len() as u16;
-                }
-            }
-            ModifyType::SurSID => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::SurPID;
-                    self.cursor.char_idx = self.input_buf[1].len() as u16;
-                }
-            }
-            ModifyType::MeshVal => {
-                if self.cursor.char_idx > 0 {
-                    self.cursor.char_idx -= 1;
-                }
+        if self.cursor.char_idx > 0 {
+            self.cursor.char_idx -= 1;
+        } else {
+            let prev_idx = self.next_field(true);
+            if prev_idx != self.cursor.field_idx {
+                self.cursor.field_idx = prev_idx;
+                self.cursor.char_idx = self.input_buf[prev_idx].value.chars().count() as u16;
             }
-            _ => {}
         }
     }
 
     fn cursor_right(&mut self) {
-        match self.cursor.modify_type {
-            ModifyType::VolName => {
-                if self.cursor.char_idx < self.input_buf[0].len() as u16 {
-                    self.cursor.char_idx += 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::VolPID;
-                    self.cursor.char_idx = 0;
-                }
-            }
-            ModifyType::VolPID => {
-                if self.cursor.char_idx < self.input_buf[1].len() as u16 {
-                    self.cursor.char_idx += 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::VolVID;
-                    self.cursor.char_idx = 0;
-                }
-            }
-            ModifyType::VolVID => {
-                if self.cursor.char_idx < self.input_buf[2].len() as u16 {
-                    self.cursor.char_idx += 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::VolName;
-                    self.cursor.char_idx = 0;
-                }
-            }
-            ModifyType::SurName => {
-                if self.cursor.char_idx < self.input_buf[0].len() as u16 {
-                    self.cursor.char_idx += 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::SurPID;
-                    self.cursor.char_idx = 0;
-                }
-            }
-            ModifyType::SurPID => {
-                if self.cursor.char_idx < self.input_buf[1].len() as u16 {
-                    self.cursor.char_idx += 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::SurSID;
-                    self.cursor.char_idx = 0;
-                }
-            }
-            ModifyType::SurSID => {
-                if self.cursor.char_idx < self.input_buf[2].len() as u16 {
-                    self.cursor.char_idx += 1;
-                } else {
-                    self.cursor.modify_type = ModifyType::SurName;
-                    self.cursor.char_idx = 0;
-                }
-            }
-            ModifyType::MeshVal => {
-                if self.cursor.char_idx < self.input_buf[1].len() as u16 {
-                    self.cursor.char_idx += 1;
-                }
+        if self.cursor.char_idx < self.input_buf[self.cursor.field_idx].value.chars().count() as u16
+        {
+            self.cursor.char_idx += 1;
+        } else {
+            let next_idx = self.next_field(false);
+            if next_idx != self.cursor.field_idx {
+                self.cursor.field_idx = next_idx;
+                self.cursor.char_idx = 0;
             }
-
-            _ => {}
         }
     }
 
+    //keep the popup open and show why the input can not be accepted
+    fn reject_modification(&mut self, error: String) {
+        self.opreation_mode = OperaMode::Modify;
+        self.input_error = Some(error);
+    }
+
     fn confirm_modification(&mut self) {
         self.opreation_mode = OperaMode::Select;
-        self.cursor.modify_type = ModifyType::None;
+        self.input_error = None;
+
+        let values: Vec<String> = self
+            .input_buf
+            .iter()
+            .map(|field| field.value.clone())
+            .collect();
 
         if let Some(selected_idx) = self.table_state.selected() {
             match self.cur_type {
                 TypeMode::Volume => {
+                    let other_groups = self
+                        .gmesh_para
+                        .vol_phy_list
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| *idx != selected_idx)
+                        .map(|(_, phys)| (phys.name.as_str(), phys.phys_id.as_str()));
+                    let phys_id = match parse_phys_id(&values[1], other_groups) {
+                        Ok(phys_id) => phys_id,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let vol_ids = match values[2].parse::<IdSet>() {
+                        Ok(vol_ids) => vol_ids,
+                        Err(e) => return self.reject_modification(format!("Volume ID: {}", e)),
+                    };
+                    let mesh_size = match parse_mesh_size(&values[3]) {
+                        Ok(mesh_size) => mesh_size,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let vol_phy = VolPhys {
+                        name: values[0].clone(),
+                        phys_id,
+                        vol_ids,
+                        mesh_size,
+                        material: values[4].clone(),
+                    };
                     if selected_idx < self.gmesh_para.vol_phy_list.len() {
                         //changing existing parameters
-                        self.gmesh_para.vol_phy_list[selected_idx] = VolPhys {
-                            name: self.input_buf[0].clone(),
-                            phys_id: self.input_buf[1].clone(),
-                            vol_ids: self.input_buf[2].clone(),
-                        };
+                        self.gmesh_para.vol_phy_list[selected_idx] = vol_phy;
                     } else {
                         //new parameters
-                        self.gmesh_para.vol_phy_list.push(VolPhys {
-                            name: self.input_buf[0].clone(),
-                            phys_id: self.input_buf[1].clone(),
-                            vol_ids: self.input_buf[2].clone(),
-                        });
+                        self.gmesh_para.vol_phy_list.push(vol_phy);
                     }
                 }
                 TypeMode::Surface => {
+                    let other_groups = self
+                        .gmesh_para
+                        .surf_phy_list
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| *idx != selected_idx)
+                        .map(|(_, phys)| (phys.name.as_str(), phys.phys_id.as_str()));
+                    let phys_id = match parse_phys_id(&values[1], other_groups) {
+                        Ok(phys_id) => phys_id,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let surf_ids = match values[2].parse::<IdSet>() {
+                        Ok(surf_ids) => surf_ids,
+                        Err(e) => return self.reject_modification(format!("Surface ID: {}", e)),
+                    };
+                    let mesh_size = match parse_mesh_size(&values[3]) {
+                        Ok(mesh_size) => mesh_size,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let thickness = values[5].trim().to_string();
+                    if !thickness.is_empty() && !thickness.parse::<f64>().is_ok_and(|t| t > 0.0) {
+                        return self.reject_modification(format!(
+                            "Thickness: \"{}\" is not a positive number",
+                            thickness
+                        ));
+                    }
+                    let surf_phy = SurfPhys {
+                        name: values[0].clone(),
+                        phys_id,
+                        surf_ids,
+                        mesh_size,
+                        boundary_conditions: self
+                            .gmesh_para
+                            .group_conditions(2, selected_idx)
+                            .cloned()
+                            .unwrap_or_default(),
+                        material: values[4].clone(),
+                        thickness,
+                    };
                     if selected_idx < self.gmesh_para.surf_phy_list.len() {
                         //changing existing parameters
-                        self.gmesh_para.surf_phy_list[selected_idx] = SurfPhys {
-                            name: self.input_buf[0].clone(),
-                            phys_id: self.input_buf[1].clone(),
-                            surf_ids: self.input_buf[2].clone(),
-                        };
+                        self.gmesh_para.surf_phy_list[selected_idx] = surf_phy;
                     } else {
                         //new parameters
-                        self.gmesh_para.surf_phy_list.push(SurfPhys {
-                            name: self.input_buf[0].clone(),
-                            phys_id: self.input_buf[1].clone(),
-                            surf_ids: self.input_buf[2].clone(),
-                        });
+                        self.gmesh_para.surf_phy_list.push(surf_phy);
                     }
                 }
-                TypeMode::Mesh => match selected_idx {
-                    0 => {
-                        self.gmesh_para.mesh_paras.max_size = self.input_buf[1].clone();
+                TypeMode::Curve => {
+                    let other_groups = self
+                        .gmesh_para
+                        .curve_phy_list
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| *idx != selected_idx)
+                        .map(|(_, phys)| (phys.name.as_str(), phys.phys_id.as_str()));
+                    let phys_id = match parse_phys_id(&values[1], other_groups) {
+                        Ok(phys_id) => phys_id,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let curve_ids = match values[2].parse::<IdSet>() {
+                        Ok(curve_ids) => curve_ids,
+                        Err(e) => return self.reject_modification(format!("Curve ID: {}", e)),
+                    };
+                    let curve_phy = CurvPhys {
+                        name: values[0].clone(),
+                        phys_id,
+                        curve_ids,
+                        boundary_layer: values[3] == "y",
+                        boundary_conditions: self
+                            .gmesh_para
+                            .group_conditions(1, selected_idx)
+                            .cloned()
+                            .unwrap_or_default(),
+                    };
+                    if selected_idx < self.gmesh_para.curve_phy_list.len() {
+                        //changing existing parameters
+                        self.gmesh_para.curve_phy_list[selected_idx] = curve_phy;
+                    } else {
+                        //new parameters
+                        self.gmesh_para.curve_phy_list.push(curve_phy);
                     }
-                    _ => {}
-                },
+                }
+                TypeMode::Point => {
+                    let other_groups = self
+                        .gmesh_para
+                        .point_phy_list
+                        .iter()
+                        .enumerate()
+                        .filter(|(idx, _)| *idx != selected_idx)
+                        .map(|(_, phys)| (phys.name.as_str(), phys.phys_id.as_str()));
+                    let phys_id = match parse_phys_id(&values[1], other_groups) {
+                        Ok(phys_id) => phys_id,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let point_ids = match values[2].parse::<IdSet>() {
+                        Ok(point_ids) => point_ids,
+                        Err(e) => return self.reject_modification(format!("Point ID: {}", e)),
+                    };
+                    let point_phy = PointPhys {
+                        name: values[0].clone(),
+                        phys_id,
+                        point_ids,
+                        boundary_conditions: self
+                            .gmesh_para
+                            .group_conditions(0, selected_idx)
+                            .cloned()
+                            .unwrap_or_default(),
+                    };
+                    if selected_idx < self.gmesh_para.point_phy_list.len() {
+                        //changing existing parameters
+                        self.gmesh_para.point_phy_list[selected_idx] = point_phy;
+                    } else {
+                        //new parameters
+                        self.gmesh_para.point_phy_list.push(point_phy);
+                    }
+                }
+                TypeMode::Entity => {
+                    let Some((dim, tag)) = self
+                        .entity_list
+                        .as_ref()
+                        .and_then(|entity_list| entity_list.entities.get(selected_idx))
+                        .map(|entity| (entity.dim, entity.tag))
+                    else {
+                        return;
+                    };
+                    let mesh_size = match parse_mesh_size(&values[1]) {
+                        Ok(mesh_size) => mesh_size,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    let nodes = values[2].trim();
+                    if !nodes.is_empty() && !nodes.parse::<u32>().is_ok_and(|n| n >= 2) {
+                        return self.reject_modification(format!(
+                            "Transfinite Nodes: \"{}\" is not an integer of 2 or more",
+                            nodes
+                        ));
+                    }
+                    self.gmesh_para.set_entity_mesh_size(dim, tag, mesh_size);
+                    self.gmesh_para.set_structured_entity(StructuredEntity {
+                        dim,
+                        tag,
+                        nodes: nodes.to_string(),
+                        recombine: values[3] == "y",
+                    });
+                }
+                TypeMode::Part => {
+                    let mut numbers = [0.0; 4];
+                    for (number, (value, field)) in numbers
+                        .iter_mut()
+                        .zip(values[1..].iter().zip(&self.input_buf[1..]))
+                    {
+                        match value.parse::<f64>() {
+                            Ok(parsed) => *number = parsed,
+                            Err(_) => {
+                                return self.reject_modification(format!(
+                                    "{}: \"{}\" is not a number",
+                                    field.title, value
+                                ));
+                            }
+                        }
+                    }
+                    let [scale, x, y, z] = numbers;
+                    let part = GeometryPart {
+                        file: values[0].trim().to_string(),
+                        scale,
+                        translation: [x, y, z],
+                    };
+
+                    let mut geometry_parts = self.gmesh_para.geometry_parts.clone();
+                    if selected_idx < geometry_parts.len() {
+                        //changing existing part
+                        geometry_parts[selected_idx] = part;
+                    } else {
+                        //new part
+                        geometry_parts.push(part);
+                    }
+                    if let Err(e) = validate_parts(&geometry_parts) {
+                        return self.reject_modification(e.to_string());
+                    }
+
+                    self.gmesh_para.geometry_parts = geometry_parts;
+                    self.start_entity_query(); //volumes are renumbered
+                }
+                TypeMode::Geometry if selected_idx == TOLERANCE_ROW => {
+                    let tolerance = values[1].trim();
+                    if !tolerance.is_empty() && !tolerance.parse::<f64>().is_ok_and(|t| t > 0.0) {
+                        return self.reject_modification(format!(
+                            "Tolerance: \"{}\" is not a positive number",
+                            tolerance
+                        ));
+                    }
+                    self.gmesh_para.healing_paras.tolerance = tolerance.to_string();
+                    self.start_entity_query(); //healing merges or splits entities
+                }
+                TypeMode::Geometry
+                    if [SOURCE_UNIT_ROW, TARGET_UNIT_ROW].contains(&selected_idx) =>
+                {
+                    let unit = match values[1].parse::<LengthUnit>() {
+                        Ok(unit) => unit,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    if selected_idx == SOURCE_UNIT_ROW {
+                        self.gmesh_para.units.source = unit;
+                    } else {
+                        self.gmesh_para.units.target = unit;
+                    }
+                    self.start_entity_query(); //bounding boxes change
+                }
+                TypeMode::Geometry if selected_idx == DIMENSION_ROW => {
+                    match values[1].parse::<ModelDimension>() {
+                        Ok(dimension) => self.gmesh_para.model_dimension = dimension,
+                        Err(e) => self.reject_modification(e),
+                    }
+                }
+                TypeMode::Geometry => {
+                    if let Some(flag) =
+                        healing_flag(&mut self.gmesh_para.healing_paras, selected_idx)
+                    {
+                        *flag = values[1] == "y";
+                        self.start_entity_query(); //healing merges or splits entities
+                    }
+                }
+                TypeMode::Mesh => {
+                    let option = &MESH_OPTIONS[selected_idx];
+                    let value = values[1].trim().to_string();
+                    if !value.is_empty()
+                        && let Err(e) = option.validate(&value)
+                    {
+                        return self.reject_modification(e);
+                    }
+                    self.gmesh_para.mesh_paras.set(option.name, value);
+                }
+                TypeMode::Advanced => {
+                    let name = values[0].trim();
+                    let value = values[1].trim();
+                    let unchecked = values[2] == "y";
+                    let warning = match validate_advanced(name, value, unchecked) {
+                        Ok(warning) => warning,
+                        Err(e) => return self.reject_modification(e),
+                    };
+                    //the same option twice would only keep the last value
+                    let advanced_options = &mut self.gmesh_para.advanced_options;
+                    if let Some(other_idx) = advanced_options
+                        .iter()
+                        .position(|option| option.name == name)
+                        && other_idx != selected_idx
+                    {
+                        return self.reject_modification(format!("{} is already set", name));
+                    }
+
+                    let option = AdvancedOption {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                        unchecked,
+                    };
+                    if selected_idx < advanced_options.len() {
+                        //changing existing option
+                        advanced_options[selected_idx] = option;
+                    } else {
+                        //new option
+                        advanced_options.push(option);
+                    }
+                    if let Some(warning) = warning {
+                        self.message = warning;
+                    }
+                }
+                TypeMode::Field => {
+                    let Some(mut field) = self.gmesh_para.fields.get(selected_idx).cloned() else {
+                        //new field, its parameters are set by pressing Enter on it again
+                        let kind = match values[0].parse::<FieldKind>() {
+                            Ok(kind) => kind,
+                            Err(e) => return self.reject_modification(e),
+                        };
+                        let id = self
+                            .gmesh_para
+                            .fields
+                            .iter()
+                            .map(|f| f.id)
+                            .max()
+                            .unwrap_or(0)
+                            + 1;
+                        self.gmesh_para.fields.push(MeshField::new(id, kind));
+                        self.message = format!("Field {} added, Enter: set its parameters", id);
+                        return;
+                    };
+
+                    for (param, value) in field.kind.params().iter().zip(&values[3..]) {
+                        if let Err(e) = param.validate(value) {
+                            return self.reject_modification(e);
+                        }
+                        field.set(param.name, value.trim().to_string());
+                    }
+
+                    //other fields are known now, surfaces may not be queried yet
+                    let mut fields = self.gmesh_para.fields.clone();
+                    fields[selected_idx] = field.clone();
+                    if let Some(problem) = field.problems(&fields, None).into_iter().next() {
+                        return self.reject_modification(problem);
+                    }
+
+                    if values[2] == "y" {
+                        self.gmesh_para.background_field = Some(field.id);
+                    } else if self.gmesh_para.background_field == Some(field.id) {
+                        self.gmesh_para.background_field = None;
+                    }
+                    self.gmesh_para.fields = fields;
+                }
+                TypeMode::Condition => {
+                    let rows = condition_rows(&self.gmesh_para);
+                    let Some(&(dim, group_idx, condition_idx)) = rows.get(selected_idx) else {
+                        //new condition, its values are set by pressing Enter on it again
+                        let groups = self.gmesh_para.condition_groups();
+                        let Some(&(dim, group_idx, name)) =
+                            condition_group_choices(&self.gmesh_para)
+                                .iter()
+                                .position(|(label, _)| *label == values[0])
+                                .and_then(|pos| groups.get(pos))
+                        else {
+                            return;
+                        };
+                        let kind = match values[1].parse::<BcKind>() {
+                            Ok(kind) => kind,
+                            Err(e) => return self.reject_modification(e),
+                        };
+                        if let Err(e) = kind.check_dim(dim, self.gmesh_para.known_mesh_dimension())
+                        {
+                            return self.reject_modification(e);
+                        }
+
+                        let label = group_label(dim, name);
+                        if let Some(conditions) =
+                            self.gmesh_para.group_conditions_mut(dim, group_idx)
+                        {
+                            conditions.push(BoundaryCondition::new(kind));
+                        }
+                        //select the new row, it sits after the other conditions of its group
+                        let new_idx = condition_rows(&self.gmesh_para).iter().rposition(
+                            |&(row_dim, row_group, _)| row_dim == dim && row_group == group_idx,
+                        );
+                        self.table_state.select(new_idx);
+                        self.message =
+                            format!("{} added to {}, Enter: set its values", kind, label);
+                        return;
+                    };
+
+                    let Some(mut condition) = self
+                        .gmesh_para
+                        .group_conditions(dim, group_idx)
+                        .and_then(|conditions| conditions.get(condition_idx))
+                        .cloned()
+                    else {
+                        return;
+                    };
+                    for (param, value) in condition.kind.params().iter().zip(&values[2..]) {
+                        condition.set(param.name, value.trim().to_string());
+                    }
+                    if let Err(e) = condition.check(self.gmesh_para.units.target) {
+                        return self.reject_modification(e);
+                    }
+                    if let Some(conditions) = self.gmesh_para.group_conditions_mut(dim, group_idx) {
+                        conditions[condition_idx] = condition;
+                    }
+                }
+                TypeMode::Layer => {
+                    let value = values[1].trim().to_string();
+                    let boundary_layer = &mut self.gmesh_para.boundary_layer;
+                    match selected_idx {
+                        WALL_CURVES_ROW => match value.parse::<IdSet>() {
+                            Ok(wall_curves) => boundary_layer.wall_curves = wall_curves,
+                            Err(e) => {
+                                self.reject_modification(format!("Wall Curves: {}", e));
+                            }
+                        },
+                        1 | 2
+                            if !value.is_empty()
+                                && !value.parse::<f64>().is_ok_and(|v| v > 0.0) =>
+                        {
+                            self.reject_modification(format!(
+                                "{}: \"{}\" is not a positive number",
+                                LAYER_OPTIONS[selected_idx], value
+                            ));
+                        }
+                        1 => boundary_layer.first_thickness = value,
+                        2 => boundary_layer.growth_ratio = value,
+                        3 if !value.is_empty() && !value.parse::<u32>().is_ok_and(|n| n > 0) => {
+                            self.reject_modification(format!(
+                                "Layer Count: \"{}\" is not a positive integer",
+                                value
+                            ));
+                        }
+                        _ => boundary_layer.layer_count = value,
+                    }
+                }
+                TypeMode::Export => {
+                    let Ok(element_tag_type @ 1..=3) = values[3].parse::<u8>() else {
+                        return self.reject_modification(format!(
+                            "Tag Type: \"{}\" is not one of 1, 2 or 3",
+                            values[3]
+                        ));
+                    };
+                    let setting = &mut self.gmesh_para.export_settings[selected_idx];
+                    setting.enabled = values[1] == "y";
+                    setting.save_all = values[2] == "y";
+                    setting.element_tag_type = element_tag_type;
+                    setting.nastran_deck = setting.format.is_nastran() && values[4] == "y";
+                }
                 _ => {}
             }
         }
     }
 }
 
-impl Widget for &mut TUI {
+//a positive size in the target unit, empty for no local size
+fn parse_mesh_size(value: &str) -> std::result::Result<String, String> {
+    let mesh_size = value.trim();
+    if mesh_size.is_empty() || mesh_size.parse::<f64>().is_ok_and(|size| size > 0.0) {
+        Ok(mesh_size.to_string())
+    } else {
+        Err(format!(
+            "Mesh Size: \"{}\" is not a positive number",
+            mesh_size
+        ))
+    }
+}
+
+//an empty id gives Physical Volume("name",)={..}, which is not valid .geo,
+//and two groups of the same dimension with one id would be merged in the mesh
+fn parse_phys_id<'a>(
+    value: &str,
+    other_groups: impl Iterator<Item = (&'a str, &'a str)>, //(name, phys_id)
+) -> std::result::Result<String, String> {
+    let phys_id = value.trim();
+    let id = match phys_id.parse::<u32>() {
+        Ok(id) if id > 0 => id,
+        _ => {
+            return Err(format!(
+                "Physical ID: \"{}\" is not a positive number",
+                phys_id
+            ));
+        }
+    };
+
+    for (name, other_id) in other_groups {
+        if other_id.trim().parse::<u32>() == Ok(id) {
+            return Err(format!("Physical ID: {} is already used by {}", id, name));
+        }
+    }
+    Ok(phys_id.to_string())
+}
+
+//materials of the library for the pickers, led by the empty choice
+fn material_choices(material_library: &MaterialLibrary) -> Dropdown {
+    let mut materials = vec![(String::new(), String::from("no material"))];
+    materials.extend(
+        material_library
+            .materials
+            .iter()
+            .map(|material| (material.name.clone(), material.summary())),
+    );
+    materials
+}
+
+fn yes_no(flag: bool) -> String {
+    String::from(if flag { "y" } else { "n" })
+}
+
+//the on/off option shown in a row of the Geometry table, None for the tolerance row
+fn healing_flag(healing: &mut HealingPara, row: usize) -> Option<&mut bool> {
+    match row {
+        0 => Some(&mut healing.boolean_fragments),
+        1 => Some(&mut healing.coherence),
+        3 => Some(&mut healing.fix_degenerated),
+        4 => Some(&mut healing.fix_small_edges),
+        5 => Some(&mut healing.fix_small_faces),
+        6 => Some(&mut healing.sew_faces),
+        7 => Some(&mut healing.make_solids),
+        _ => None,
+    }
+}
+
+fn geometry_value(gmsh_para: &GmshPara, row: usize) -> String {
+    match row {
+        TOLERANCE_ROW => gmsh_para.healing_paras.tolerance.clone(),
+        SOURCE_UNIT_ROW => gmsh_para.units.source.to_string(),
+        TARGET_UNIT_ROW => gmsh_para.units.target.to_string(),
+        DIMENSION_ROW => gmsh_para.model_dimension.to_string(),
+        _ => match healing_flag(&mut gmsh_para.healing_paras.clone(), row) {
+            Some(flag) => yes_no(*flag),
+            None => String::new(),
+        },
+    }
+}
+
+impl Widget for &mut Tui {
     fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized,
     {
-        let [up_area, bottom_area] =
-            Layout::vertical(vec![Constraint::Percentage(90), Constraint::Percentage(10)])
-                .areas(area);
+        let [up_area, log_area, bottom_area] = Layout::vertical(vec![
+            Constraint::Percentage(65),
+            Constraint::Percentage(25),
+            Constraint::Percentage(10),
+        ])
+        .areas(area);
 
-        let [vol_area, surf_area, mesh_area] = Layout::horizontal(vec![
-            Constraint::Percentage(37),
-            Constraint::Percentage(37),
-            Constraint::Percentage(26),
+        let [vol_area, surf_area, entity_area, right_area] = Layout::horizontal(vec![
+            Constraint::Percentage(27),
+            Constraint::Percentage(27),
+            Constraint::Percentage(18),
+            Constraint::Percentage(28),
         ])
         .spacing(Spacing::Overlap(1))
         .areas(up_area);
 
+        //curve and point groups below the volume and surface groups
+        let [vol_area, curve_area] =
+            Layout::vertical(vec![Constraint::Percentage(60), Constraint::Fill(1)])
+                .spacing(Spacing::Overlap(1))
+                .areas(vol_area);
+        let [surf_area, point_area] =
+            Layout::vertical(vec![Constraint::Percentage(60), Constraint::Fill(1)])
+                .spacing(Spacing::Overlap(1))
+                .areas(surf_area);
+
+        let [entity_area, warning_area] =
+            Layout::vertical(vec![Constraint::Percentage(60), Constraint::Fill(1)])
+                .spacing(Spacing::Overlap(1))
+                .areas(entity_area);
+
+        //the right column keeps showing the last selected settings table
+        if self.cur_type.is_settings_tab() {
+            self.settings_tab = self.cur_type;
+        }
+
         //instructions
 
         let [bottom_left, bottom_right] =
             Layout::horizontal([Constraint::Percentage(30), Constraint::Fill(1)])
                 .areas(bottom_area);
 
-        Line::from(String::from("Geometry File: ") + &self.gmesh_para.geometry_file)
+        let [geometry_line, message_line, status_line] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(bottom_left);
+
+        let geometry_files: Vec<String> = self
+            .gmesh_para
+            .geometry_parts
+            .iter()
+            .map(GeometryPart::name)
+            .collect();
+        Line::from(String::from("Geometry: ") + &geometry_files.join(", "))
             .blue()
-            .render(bottom_left, buf);
+            .render(geometry_line, buf);
+
+        Line::from(self.message.clone())
+            .green()
+            .render(message_line, buf);
+
+        self.gmsh_status.line().render(status_line, buf);
 
         match self.opreation_mode {
             OperaMode::Select => {
-                Line::from("| Esc: quit | ↑↓←→: select | Enter: modify | A: apply to Gmsh | Ctrl+A: apply & save to .nas |")
+                Line::from("| Esc: quit | ↑↓←→: select | Enter: modify | A: apply to Gmsh | Ctrl+A: apply & export | P/Ctrl+P: preview script | C: cancel Gmsh | Q: query entities | Ctrl+S: save project |")
                     .yellow()
                     .render(bottom_right, buf);
             }
@@ -798,28 +2001,61 @@ impl Widget for &mut TUI {
                     .yellow()
                     .render(bottom_right, buf);
             }
+            OperaMode::Preview => {
+                Line::from("| Esc: close | ↑↓: scroll |")
+                    .yellow()
+                    .render(bottom_right, buf);
+            }
         }
 
         //main UI rendering
 
+        //a shell model is meshed on the surfaces, its curves bound them
+        let (vol_title, surf_title, curve_title) = match self.gmesh_para.mesh_dimension() {
+            2 => (
+                "Physical Volume (not meshed in 2D)",
+                "Physical Surface (Shells)",
+                "Physical Curve (Boundaries)",
+            ),
+            _ => ("Physical Volume", "Physical Surface", "Physical Curve"),
+        };
+
         let vol_block = Block::new()
-            .title("Physical Volume")
+            .title(vol_title)
             .borders(Borders::ALL)
             .merge_borders(MergeStrategy::Exact);
 
         let surf_block = Block::new()
-            .title("Physical Surface")
+            .title(surf_title)
+            .borders(Borders::ALL)
+            .merge_borders(MergeStrategy::Exact);
+
+        let curve_block = Block::new()
+            .title(curve_title)
+            .borders(Borders::ALL)
+            .merge_borders(MergeStrategy::Exact);
+
+        let point_block = Block::new()
+            .title("Physical Point")
             .borders(Borders::ALL)
             .merge_borders(MergeStrategy::Exact);
 
-        let mesh_block = Block::new()
-            .title("Mesh Parameters")
+        let settings_block = Block::new()
+            .title(settings_tabs_line(self.settings_tab))
             .borders(Borders::ALL)
             .merge_borders(MergeStrategy::Exact);
 
         //Physical Volume Table
         let mut vol_rows = Vec::new();
-        row_convertion_vol(&self.gmesh_para.vol_phy_list, &mut vol_rows);
+        row_convertion_vol(
+            &self.gmesh_para.vol_phy_list,
+            self.entity_list.as_ref(),
+            &self.material_library,
+            &mut vol_rows,
+        );
+
+        //local mesh sizes are in the target unit
+        let size_header = format!("Size ({})", self.gmesh_para.units.target);
 
         let vol_table = Table::new(
             vol_rows,
@@ -827,15 +2063,23 @@ impl Widget for &mut TUI {
                 Constraint::Percentage(15),
                 Constraint::Percentage(15),
                 Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(14),
             ],
         )
+        .header(Row::new(["Name", "ID", "Volumes", &size_header, "Material"]).bold())
         .block(vol_block)
         .row_highlight_style(Style::new().reversed())
         .highlight_symbol(">>");
 
         //Physical Surface Table
         let mut surf_rows = Vec::new();
-        row_convertion_surf(&self.gmesh_para.surf_phy_list, &mut surf_rows);
+        row_convertion_surf(
+            &self.gmesh_para.surf_phy_list,
+            self.entity_list.as_ref(),
+            &self.material_library,
+            &mut surf_rows,
+        );
 
         let surf_table = Table::new(
             surf_rows,
@@ -843,199 +2087,965 @@ impl Widget for &mut TUI {
                 Constraint::Percentage(15),
                 Constraint::Percentage(15),
                 Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(12),
             ],
         )
+        .header(Row::new(["Name", "ID", "Surfaces", &size_header, "BCs"]).bold())
         .block(surf_block)
         .row_highlight_style(Style::new().reversed())
         .highlight_symbol(">>");
 
+        //Physical Curve Table
+        let mut curve_rows = Vec::new();
+        row_convertion_curve(&self.gmesh_para.curve_phy_list, &mut curve_rows);
+
+        let curve_table = Table::new(
+            curve_rows,
+            vec![
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Fill(1),
+                Constraint::Length(2),
+            ],
+        )
+        .header(Row::new(["Name", "ID", "Curves", "BL"]).bold())
+        .block(curve_block)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Physical Point Table
+        let mut point_rows = Vec::new();
+        row_convertion_ids(
+            self.gmesh_para
+                .point_phy_list
+                .iter()
+                .map(|point_phy| (&point_phy.name, &point_phy.phys_id, &point_phy.point_ids)),
+            &mut point_rows,
+        );
+
+        let point_table = Table::new(
+            point_rows,
+            vec![
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["Name", "ID", "Points"]).bold())
+        .block(point_block)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Entity Table
+        let mut entity_block = Block::new()
+            .title("Entities")
+            .borders(Borders::ALL)
+            .merge_borders(MergeStrategy::Exact);
+
+        let mut entity_rows = Vec::new();
+        if self.entity_query.is_some() {
+            //status text goes in the widest column
+            entity_rows.push(Row::new(["", "", "Querying..."]));
+        } else if let Some(error) = &self.entity_error {
+            entity_rows.push(Row::new([String::new(), String::new(), error.clone()]).red());
+        } else if let Some(entity_list) = &self.entity_list {
+            row_convertion_entity(entity_list, &self.gmesh_para, &mut entity_rows);
+
+            //bounding box of the selected entity
+            if let TypeMode::Entity = self.cur_type
+                && let Some(entity) = self
+                    .table_state
+                    .selected()
+                    .and_then(|idx| entity_list.entities.get(idx))
+            {
+                let [x0, y0, z0, x1, y1, z1] = entity.bbox;
+                entity_block = entity_block.title_bottom(format!(
+                    "({:.4},{:.4},{:.4})-({:.4},{:.4},{:.4})",
+                    x0, y0, z0, x1, y1, z1
+                ));
+            }
+        }
+
+        let entity_table = Table::new(
+            entity_rows,
+            vec![
+                Constraint::Length(4),
+                Constraint::Length(6),
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Length(6),
+            ],
+        )
+        .header(Row::new(["Dim", "Tag", "Volume/Part", "Size", "Struct"]).bold())
+        .block(entity_block)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Geometry Parts Table
+        let mut part_rows = Vec::new();
+        row_convertion_part(&self.gmesh_para.geometry_parts, &mut part_rows);
+
+        let part_table = Table::new(
+            part_rows,
+            vec![
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                String::from("File"),
+                String::from("Scale"),
+                format!("Move ({})", self.gmesh_para.units.target),
+            ])
+            .bold(),
+        )
+        .block(settings_block.clone())
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Geometry Table, healing options
+        let mut geometry_rows = Vec::new();
+        row_convertion_geometry(
+            &self.gmesh_para,
+            self.entity_query.is_some(),
+            &mut geometry_rows,
+        );
+
+        let geometry_table = Table::new(
+            geometry_rows,
+            vec![Constraint::Percentage(50), Constraint::Fill(1)],
+        )
+        .block(settings_block.clone())
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
         //Mesh Parameters Table
         let mut mesh_rows = Vec::new();
-        row_convertion_mesh(&self.gmesh_para.mesh_paras, &mut mesh_rows);
+        row_convertion_mesh(
+            &self.gmesh_para.mesh_paras,
+            self.gmesh_para.units.target,
+            &mut mesh_rows,
+        );
+
+        //what the selected option does
+        let mut mesh_block = settings_block.clone();
+        if let TypeMode::Mesh = self.cur_type
+            && let Some(option) = self
+                .table_state
+                .selected()
+                .and_then(|idx| MESH_OPTIONS.get(idx))
+        {
+            mesh_block = mesh_block.title_bottom(option.description);
+        }
 
         let mesh_table = Table::new(
             mesh_rows,
-            vec![Constraint::Percentage(30), Constraint::Fill(1)],
+            vec![Constraint::Percentage(50), Constraint::Fill(1)],
         )
         .block(mesh_block)
         .row_highlight_style(Style::new().reversed())
         .highlight_symbol(">>");
 
+        //Advanced Options Table
+        let mut advanced_rows = Vec::new();
+        row_convertion_advanced(&self.gmesh_para.advanced_options, &mut advanced_rows);
+
+        let mut advanced_block = settings_block.clone();
+        if let TypeMode::Advanced = self.cur_type
+            && let Some(option) = self
+                .table_state
+                .selected()
+                .and_then(|idx| self.gmesh_para.advanced_options.get(idx))
+                .and_then(|option| find_option(&option.name))
+        {
+            advanced_block = advanced_block.title_bottom(option.description);
+        }
+
+        let advanced_table = Table::new(
+            advanced_rows,
+            vec![Constraint::Percentage(70), Constraint::Fill(1)],
+        )
+        .block(advanced_block)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Mesh Size Fields Table
+        let mut field_rows = Vec::new();
+        row_convertion_field(&self.gmesh_para, self.entity_list.as_ref(), &mut field_rows);
+
+        //what is wrong with the selected field, or which field sets the size
+        let selected_field_problem = match self.cur_type {
+            TypeMode::Field => self
+                .table_state
+                .selected()
+                .and_then(|idx| self.gmesh_para.fields.get(idx))
+                .and_then(|field| {
+                    field
+                        .problems(&self.gmesh_para.fields, self.entity_list.as_ref())
+                        .into_iter()
+                        .next()
+                }),
+            _ => None,
+        };
+        let field_status = match (selected_field_problem, self.gmesh_para.background_field) {
+            (Some(problem), _) => Line::from(problem).red(),
+            (None, Some(background_field)) => {
+                Line::from(format!("* Background Field: {}", background_field))
+            }
+            (None, None) => Line::from("No background field"),
+        };
+
+        let field_table = Table::new(
+            field_rows,
+            vec![
+                Constraint::Length(5),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["ID", "Type", "Parameters"]).bold())
+        .block(settings_block.clone().title_bottom(field_status))
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Boundary Layers Table
+        let mut layer_rows = Vec::new();
+        row_convertion_layer(
+            &self.gmesh_para.boundary_layer,
+            self.gmesh_para.units.target,
+            &mut layer_rows,
+        );
+
+        //which curves are walls, or why the layers can not be made
+        let walls = self.gmesh_para.boundary_layer_walls();
+        let layer_status = match self.gmesh_para.check_boundary_layer() {
+            Err(e) => Line::from(e.to_string()).red(),
+            Ok(()) if walls.is_empty() => Line::from("No walls"),
+            Ok(()) => {
+                let total = self
+                    .gmesh_para
+                    .boundary_layer
+                    .heights()
+                    .and_then(|heights| heights.last().copied())
+                    .unwrap_or(0.0);
+                Line::from(format!("Walls: {}, total thickness {:.4}", walls, total))
+            }
+        };
+
+        let layer_table = Table::new(
+            layer_rows,
+            vec![Constraint::Percentage(50), Constraint::Fill(1)],
+        )
+        .block(settings_block.clone().title_bottom(layer_status))
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Boundary Conditions Table
+        let mut condition_table_rows = Vec::new();
+        row_convertion_condition(&self.gmesh_para, &mut condition_table_rows);
+
+        //what is wrong with the selected condition
+        let condition_problem = match self.cur_type {
+            TypeMode::Condition => self
+                .table_state
+                .selected()
+                .and_then(|idx| condition_rows(&self.gmesh_para).get(idx).copied())
+                .and_then(|(dim, group_idx, condition_idx)| {
+                    self.gmesh_para.group_conditions(dim, group_idx)?[condition_idx]
+                        .check(self.gmesh_para.units.target)
+                        .err()
+                }),
+            _ => None,
+        };
+        let condition_status = match condition_problem {
+            Some(problem) => Line::from(problem).red(),
+            None => Line::from(format!(
+                "bare numbers in N, s, K and {}",
+                self.gmesh_para.units.target
+            )),
+        };
+
+        let condition_table = Table::new(
+            condition_table_rows,
+            vec![
+                Constraint::Percentage(30),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["Group", "Type", "Values"]).bold())
+        .block(settings_block.clone().title_bottom(condition_status))
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
+        //Export Formats Table
+        let mut export_rows = Vec::new();
+        row_convertion_export(&self.gmesh_para.export_settings, &mut export_rows);
+
+        let export_table = Table::new(
+            export_rows,
+            vec![
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(4),
+                Constraint::Length(4),
+                Constraint::Length(4),
+            ],
+        )
+        .header(Row::new(["", "Format", "All", "Tag", "Deck"]).bold())
+        .block(settings_block)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol(">>");
+
         let mut vol_state = TableState::new();
         let mut surf_state = TableState::new();
-        let mut mesh_state = TableState::new();
+        let mut curve_state = TableState::new();
+        let mut point_state = TableState::new();
+        let mut entity_state = TableState::new();
+        let mut settings_state = TableState::new();
         match self.cur_type {
-            TypeMode::Volume => vol_state = self.table_state.clone(),
-            TypeMode::Surface => surf_state = self.table_state.clone(),
-            TypeMode::Mesh => mesh_state = self.table_state.clone(),
+            TypeMode::Volume => vol_state = self.table_state,
+            TypeMode::Surface => surf_state = self.table_state,
+            TypeMode::Curve => curve_state = self.table_state,
+            TypeMode::Point => point_state = self.table_state,
+            TypeMode::Entity => entity_state = self.table_state,
+            mode if mode.is_settings_tab() => settings_state = self.table_state,
             _ => {}
         }
 
         StatefulWidget::render(vol_table, vol_area, buf, &mut vol_state);
         StatefulWidget::render(surf_table, surf_area, buf, &mut surf_state);
-        StatefulWidget::render(mesh_table, mesh_area, buf, &mut mesh_state);
+        StatefulWidget::render(curve_table, curve_area, buf, &mut curve_state);
+        StatefulWidget::render(point_table, point_area, buf, &mut point_state);
+        StatefulWidget::render(entity_table, entity_area, buf, &mut entity_state);
+        self.render_coverage(warning_area, buf);
+        let settings_table = match self.settings_tab {
+            TypeMode::Part => part_table,
+            TypeMode::Geometry => geometry_table,
+            TypeMode::Mesh => mesh_table,
+            TypeMode::Advanced => advanced_table,
+            TypeMode::Field => field_table,
+            TypeMode::Layer => layer_table,
+            TypeMode::Condition => condition_table,
+            _ => export_table,
+        };
+        StatefulWidget::render(settings_table, right_area, buf, &mut settings_state);
+        self.render_log(log_area, buf);
 
         //render popup dialog in OpreaMode::Modify
         if let OperaMode::Modify = self.opreation_mode {
             let popup_area = popup_area(area, 60, 3);
             Widget::render(Clear, popup_area, buf); //clean the background for popup
 
-            match self.cur_type {
-                TypeMode::Volume => {
-                    let [name_area, pid_area, vid_area] = Layout::horizontal([
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Fill(1),
-                    ])
-                    .spacing(Spacing::Overlap(1))
-                    .areas(popup_area);
-
-                    let name_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Name");
-                    let pid_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Physical ID");
-                    let vid_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Volume ID");
-
-                    Paragraph::new(self.input_buf[0].clone())
-                        .block(name_block)
-                        .render(name_area, buf);
-                    Paragraph::new(self.input_buf[1].clone())
-                        .block(pid_block)
-                        .render(pid_area, buf);
-                    Paragraph::new(self.input_buf[2].clone())
-                        .block(vid_block)
-                        .render(vid_area, buf);
-
-                    //set cursor
-                    match self.cursor.modify_type {
-                        ModifyType::VolName => {
-                            self.cursor.begin_x = name_area.x + 1;
-                            self.cursor.begin_y = name_area.y + 1;
-                        }
-                        ModifyType::VolPID => {
-                            self.cursor.begin_x = pid_area.x + 1;
-                            self.cursor.begin_y = pid_area.y + 1;
-                        }
-                        ModifyType::VolVID => {
-                            self.cursor.begin_x = vid_area.x + 1;
-                            self.cursor.begin_y = vid_area.y + 1;
-                        }
-                        _ => {}
-                    }
+            let field_areas = Layout::horizontal(self.input_buf.iter().map(|field| field.width))
+                .spacing(Spacing::Overlap(1))
+                .split(popup_area);
+
+            for (field, field_area) in self.input_buf.iter().zip(field_areas.iter()) {
+                let field_block = Block::bordered()
+                    .merge_borders(MergeStrategy::Exact)
+                    .title(field.title.as_str());
+
+                Paragraph::new(field.display_value())
+                    .block(field_block)
+                    .render(*field_area, buf);
+            }
+
+            //show the rejected input reason right below the popup
+            let mut below_popup = popup_area.bottom();
+            if let Some(error) = &self.input_error {
+                let error_area = Rect {
+                    y: below_popup,
+                    height: 1,
+                    ..popup_area
                 }
-                TypeMode::Surface => {
-                    let [name_area, pid_area, sid_area] = Layout::horizontal([
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Fill(1),
-                    ])
-                    .spacing(Spacing::Overlap(1))
-                    .areas(popup_area);
-
-                    let name_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Name");
-                    let pid_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Physical ID");
-                    let sid_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Surface ID");
-
-                    Paragraph::new(self.input_buf[0].clone())
-                        .block(name_block)
-                        .render(name_area, buf);
-                    Paragraph::new(self.input_buf[1].clone())
-                        .block(pid_block)
-                        .render(pid_area, buf);
-                    Paragraph::new(self.input_buf[2].clone())
-                        .block(sid_block)
-                        .render(sid_area, buf);
-
-                    //set cursor
-                    match self.cursor.modify_type {
-                        ModifyType::SurName => {
-                            self.cursor.begin_x = name_area.x + 1;
-                            self.cursor.begin_y = name_area.y + 1;
-                        }
-                        ModifyType::SurPID => {
-                            self.cursor.begin_x = pid_area.x + 1;
-                            self.cursor.begin_y = pid_area.y + 1;
-                        }
-                        ModifyType::SurSID => {
-                            self.cursor.begin_x = sid_area.x + 1;
-                            self.cursor.begin_y = sid_area.y + 1;
-                        }
-                        _ => {}
-                    }
+                .intersection(area);
+                Widget::render(Clear, error_area, buf);
+                Line::from(error.clone()).red().render(error_area, buf);
+                below_popup += 1;
+            }
+
+            //list the choices or completions under the field being edited
+            let (dropdown, highlighted) = self.input_buf[self.cursor.field_idx].dropdown();
+            if !dropdown.is_empty() {
+                let value_width = dropdown
+                    .iter()
+                    .map(|(value, _)| value.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                let meaning_width = dropdown
+                    .iter()
+                    .map(|(_, meaning)| meaning.chars().count())
+                    .max()
+                    .unwrap_or(0);
+
+                //as wide as the entries need, shifted left when it would leave the screen
+                let field_area = field_areas[self.cursor.field_idx];
+                let width = ((value_width + meaning_width + 3) as u16)
+                    .max(field_area.width)
+                    .min(area.width);
+                let dropdown_area = Rect {
+                    x: field_area.x.min(area.right() - width),
+                    y: below_popup,
+                    width,
+                    height: dropdown.len().min(DROPDOWN_ROWS) as u16 + 2,
                 }
-                TypeMode::Mesh => {
-                    let [name_area, val_area] =
-                        Layout::horizontal([Constraint::Percentage(40), Constraint::Fill(1)])
-                            .spacing(Spacing::Overlap(1))
-                            .areas(popup_area);
-
-                    let name_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Name");
-                    let val_block = Block::bordered()
-                        .merge_borders(MergeStrategy::Exact)
-                        .title("Value");
-
-                    Paragraph::new(self.input_buf[0].clone())
-                        .block(name_block)
-                        .render(name_area, buf);
-
-                    Paragraph::new(self.input_buf[1].clone())
-                        .block(val_block)
-                        .render(val_area, buf);
-
-                    //set cursor
-                    match self.cursor.modify_type {
-                        ModifyType::MeshVal => {
-                            self.cursor.begin_x = val_area.x + 1;
-                            self.cursor.begin_y = val_area.y + 1;
-                        }
-                        _ => {}
-                    }
+                .intersection(area);
+
+                let dropdown_rows: Vec<Row> = dropdown
+                    .iter()
+                    .map(|(value, meaning)| Row::new([value.to_string(), meaning.to_string()]))
+                    .collect();
+                let mut dropdown_state = TableState::new().with_selected(highlighted);
+
+                Widget::render(Clear, dropdown_area, buf);
+                StatefulWidget::render(
+                    Table::new(
+                        dropdown_rows,
+                        [Constraint::Length(value_width as u16), Constraint::Fill(1)],
+                    )
+                    .block(Block::bordered())
+                    .row_highlight_style(Style::new().reversed()),
+                    dropdown_area,
+                    buf,
+                    &mut dropdown_state,
+                );
+            }
+
+            //set cursor
+            let cursor_area = field_areas[self.cursor.field_idx];
+            self.cursor.begin_x = cursor_area.x + 1;
+            self.cursor.begin_y = cursor_area.y + 1;
+        }
+
+        //render script preview in OperaMode::Preview
+        if let OperaMode::Preview = self.opreation_mode {
+            let [preview_area] = Layout::vertical([Constraint::Percentage(80)])
+                .flex(layout::Flex::Center)
+                .areas(area);
+            let [preview_area] = Layout::horizontal([Constraint::Percentage(80)])
+                .flex(layout::Flex::Center)
+                .areas(preview_area);
+            Widget::render(Clear, preview_area, buf); //clean the background for popup
+
+            Paragraph::new(self.preview_script.clone())
+                .block(Block::bordered().title("Script Preview"))
+                .scroll((self.preview_scroll, 0))
+                .render(preview_area, buf);
+        }
+
+        if let Some(error) = &self.error_popup {
+            let error_area = popup_area(area, 60, 5);
+            Widget::render(Clear, error_area, buf); //clean the background for popup
+
+            Paragraph::new(error.to_string())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title("Error")
+                        .title_bottom("Enter/Esc: dismiss")
+                        .red(),
+                )
+                .render(error_area, buf);
+        }
+    }
+}
+
+impl Tui {
+    //Gmsh output, follows the newest line unless the log table is selected
+    fn render_log(&self, area: Rect, buf: &mut Buffer) {
+        let log_lines = self.gmsh_log.lines();
+
+        let mut log_block = Block::new()
+            .title(format!(
+                "Gmsh Log ({} warnings, {} errors)",
+                self.gmsh_log.count(LogLevel::Warning),
+                self.gmsh_log.count(LogLevel::Error)
+            ))
+            .borders(Borders::ALL);
+        if let Some(failure) = self.gmsh_log.failure() {
+            log_block = log_block.title_bottom(Line::from(format!("FAILED: {}", failure)).red());
+        }
+
+        let visible_rows = area.height.saturating_sub(2) as usize;
+        let mut log_state = match self.cur_type {
+            TypeMode::Log => self.table_state,
+            _ => TableState::new().with_offset(log_lines.len().saturating_sub(visible_rows)),
+        };
+
+        let log_rows: Vec<Row> = log_lines
+            .into_iter()
+            .map(|line| {
+                let row = Row::new([line.text]);
+                match line.level {
+                    LogLevel::Warning => row.yellow(),
+                    LogLevel::Error => row.red(),
+                    _ => row,
+                }
+            })
+            .collect();
+
+        let log_table = Table::new(log_rows, [Constraint::Fill(1)])
+            .block(log_block)
+            .row_highlight_style(Style::new().reversed());
+
+        StatefulWidget::render(log_table, area, buf, &mut log_state);
+    }
+
+    //unassigned and doubly assigned entities, only known after the entity query
+    fn render_coverage(&self, area: Rect, buf: &mut Buffer) {
+        let warning_block = Block::new()
+            .title("Coverage")
+            .borders(Borders::ALL)
+            .merge_borders(MergeStrategy::Exact);
+
+        let lines: Vec<Line> = match &self.entity_list {
+            Some(entity_list) => {
+                let report = check_coverage(&self.gmesh_para, entity_list);
+                if report.is_clean() {
+                    vec![Line::from("All entities assigned").green()]
+                } else {
+                    report
+                        .warnings()
+                        .into_iter()
+                        .map(|warning| Line::from(warning).yellow())
+                        .collect()
                 }
-                TypeMode::None => {}
             }
+            None => vec![Line::from("Waiting for entities")],
+        };
+
+        Paragraph::new(lines)
+            .block(warning_block)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
+
+fn row_convertion_vol(
+    vol_list: &[VolPhys],
+    entity_list: Option<&EntityList>,
+    material_library: &MaterialLibrary,
+    rows: &mut Vec<Row>,
+) {
+    rows.clear();
+
+    for vol_phy in vol_list.iter().cloned() {
+        let (ids, missing) = ids_with_missing(&vol_phy.vol_ids, 3, entity_list);
+        //a material removed from the library file
+        let unknown_material =
+            !vol_phy.material.is_empty() && material_library.get(&vol_phy.material).is_none();
+        let row = Row::new(vec![
+            vol_phy.name,
+            vol_phy.phys_id,
+            ids,
+            vol_phy.mesh_size,
+            vol_phy.material,
+        ]);
+        rows.push(if missing || unknown_material {
+            row.red()
+        } else {
+            row
+        });
+    }
+    rows.push(Row::new(["Enter", "To", "Add"]));
+}
+
+fn row_convertion_surf(
+    surf_list: &[SurfPhys],
+    entity_list: Option<&EntityList>,
+    material_library: &MaterialLibrary,
+    rows: &mut Vec<Row>,
+) {
+    rows.clear();
+
+    for surf_phy in surf_list.iter().cloned() {
+        let (ids, missing) = ids_with_missing(&surf_phy.surf_ids, 2, entity_list);
+        let unknown_material =
+            !surf_phy.material.is_empty() && material_library.get(&surf_phy.material).is_none();
+        let row = Row::new(vec![
+            surf_phy.name,
+            surf_phy.phys_id,
+            ids,
+            surf_phy.mesh_size,
+            conditions_label(&surf_phy.boundary_conditions),
+        ]);
+        rows.push(if missing || unknown_material {
+            row.red()
+        } else {
+            row
+        });
+    }
+
+    rows.push(Row::new(["Enter", "To", "Add"]));
+}
+
+//curves are not queried, so their ids are shown as they are
+fn row_convertion_curve(curve_list: &[CurvPhys], rows: &mut Vec<Row>) {
+    rows.clear();
+
+    for curve_phy in curve_list {
+        rows.push(Row::new(vec![
+            curve_phy.name.clone(),
+            curve_phy.phys_id.clone(),
+            curve_phy.curve_ids.to_string(),
+            String::from(if curve_phy.boundary_layer { "y" } else { "" }),
+        ]));
+    }
+
+    rows.push(Row::new(["Enter", "To", "Add"]));
+}
+
+//points are not queried, so their ids are shown as they are
+fn row_convertion_ids<'a>(
+    group_list: impl Iterator<Item = (&'a String, &'a String, &'a IdSet)>,
+    rows: &mut Vec<Row<'a>>,
+) {
+    rows.clear();
+
+    for (name, phys_id, ids) in group_list {
+        rows.push(Row::new(vec![
+            name.clone(),
+            phys_id.clone(),
+            ids.to_string(),
+        ]));
+    }
+
+    rows.push(Row::new(["Enter", "To", "Add"]));
+}
+
+//ids for display, with the ids not existing in the model flagged
+fn ids_with_missing(ids: &IdSet, dim: u8, entity_list: Option<&EntityList>) -> (String, bool) {
+    let missing = match entity_list {
+        Some(entity_list) => entity_list.missing(dim, ids),
+        None => IdSet::new(), //not known yet
+    };
+
+    if missing.is_empty() {
+        (ids.to_string(), false)
+    } else {
+        (format!("{} (missing: {})", ids, missing), true)
+    }
+}
+
+//surfaces show the volumes they bound, volumes and free surfaces show the parts they come from
+fn row_convertion_entity(entity_list: &EntityList, gmsh_para: &GmshPara, rows: &mut Vec<Row>) {
+    let parts = &gmsh_para.geometry_parts;
+    rows.clear();
+
+    for entity in &entity_list.entities {
+        let dim = if entity.dim == 3 { "V" } else { "S" };
+        let relation = if entity.dim == 3 || entity.parents.is_empty() {
+            let part_names: Vec<String> = entity
+                .parts
+                .iter()
+                .filter_map(|part_idx| parts.get(*part_idx))
+                .map(GeometryPart::name)
+                .collect();
+            part_names.join("+")
+        } else {
+            let parents: Vec<String> = entity.parents.iter().map(|tag| tag.to_string()).collect();
+            parents.join(",")
+        };
+        rows.push(Row::new(vec![
+            dim.to_string(),
+            entity.tag.to_string(),
+            relation,
+            gmsh_para
+                .entity_mesh_size(entity.dim, entity.tag)
+                .to_string(),
+            structured_summary(gmsh_para.structured_entity(entity.dim, entity.tag)),
+        ]));
+    }
+}
+
+//node count, R when recombined, eg. "10 R"
+fn structured_summary(structured: Option<&StructuredEntity>) -> String {
+    let Some(structured) = structured else {
+        return String::new();
+    };
+    let recombine = if structured.recombine { "R" } else { "" };
+    format!("{} {}", structured.nodes, recombine)
+        .trim()
+        .to_string()
+}
+
+fn row_convertion_geometry(gmsh_para: &GmshPara, querying: bool, rows: &mut Vec<Row>) {
+    rows.clear();
+
+    for (row, name) in GEOMETRY_OPTIONS.iter().enumerate() {
+        let value = geometry_value(gmsh_para, row);
+        let value = match (row, value.is_empty()) {
+            (TOLERANCE_ROW, true) => String::from("(Gmsh default)"),
+            //show what auto has found
+            (DIMENSION_ROW, _) if querying && gmsh_para.dimension_pending() => {
+                format!("{} (detecting)", value)
+            }
+            (DIMENSION_ROW, _) if gmsh_para.model_dimension == ModelDimension::Auto => {
+                format!("{} ({}D)", value, gmsh_para.mesh_dimension())
+            }
+            _ => value,
+        };
+        rows.push(Row::new(vec![name.to_string(), value]));
+    }
+}
+
+fn row_convertion_part(parts: &[GeometryPart], rows: &mut Vec<Row>) {
+    rows.clear();
+
+    for part in parts {
+        let [x, y, z] = part.translation;
+        rows.push(Row::new(vec![
+            part.name(),
+            part.scale.to_string(),
+            format!("{}, {}, {}", x, y, z),
+        ]));
+    }
+
+    rows.push(Row::new(["Enter", "To", "Add"]));
+}
+
+fn row_convertion_advanced(options: &[AdvancedOption], rows: &mut Vec<Row>) {
+    rows.clear();
+
+    for option in options {
+        //options outside the catalog are passed unchecked, when the user asked for it
+        match find_option(&option.name) {
+            Some(known) => rows.push(Row::new(vec![
+                option.name.clone(),
+                known.describe_value(&option.value),
+            ])),
+            None => rows.push(Row::new(vec![option.name.clone(), option.value.clone()]).yellow()),
         }
     }
+
+    rows.push(Row::new(["Enter To Add", ""]));
+}
+
+fn layer_value(boundary_layer: &BoundaryLayer, row: usize) -> String {
+    match row {
+        WALL_CURVES_ROW => boundary_layer.wall_curves.to_string(),
+        1 => boundary_layer.first_thickness.clone(),
+        2 => boundary_layer.growth_ratio.clone(),
+        _ => boundary_layer.layer_count.clone(),
+    }
 }
 
-fn row_convertion_vol(vol_list: &Vec<VolPhys>, rows: &mut Vec<Row>) {
+//the thickness is in the target unit, walls of the marked curve groups are not listed here
+fn row_convertion_layer(boundary_layer: &BoundaryLayer, unit: LengthUnit, rows: &mut Vec<Row>) {
     rows.clear();
 
-    for vol_phy in vol_list.clone() {
-        let row = Row::new(vec![vol_phy.name, vol_phy.phys_id, vol_phy.vol_ids]);
-        rows.push(row);
+    for (row, name) in LAYER_OPTIONS.iter().enumerate() {
+        let name = match row {
+            1 => format!("{} ({})", name, unit),
+            _ => name.to_string(),
+        };
+        rows.push(Row::new(vec![name, layer_value(boundary_layer, row)]));
     }
+}
+
+//the background field is marked with *, fields referring to missing ids are red
+fn row_convertion_field(
+    gmsh_para: &GmshPara,
+    entity_list: Option<&EntityList>,
+    rows: &mut Vec<Row>,
+) {
+    rows.clear();
+
+    for field in &gmsh_para.fields {
+        let id = if gmsh_para.background_field == Some(field.id) {
+            format!("{} *", field.id)
+        } else {
+            field.id.to_string()
+        };
+        let row = Row::new(vec![id, field.kind.to_string(), field.summary()]);
+        if field.problems(&gmsh_para.fields, entity_list).is_empty() {
+            rows.push(row);
+        } else {
+            rows.push(row.red());
+        }
+    }
+
     rows.push(Row::new(["Enter", "To", "Add"]));
 }
 
-fn row_convertion_surf(surf_list: &Vec<SurfPhys>, rows: &mut Vec<Row>) {
+//(dim, group index, condition index) of each row of the BCs table, grouped by group
+fn condition_rows(gmsh_para: &GmshPara) -> Vec<(u8, usize, usize)> {
+    gmsh_para
+        .condition_groups()
+        .into_iter()
+        .flat_map(|(dim, group_idx, _)| {
+            let count = gmsh_para
+                .group_conditions(dim, group_idx)
+                .map_or(0, Vec::len);
+            (0..count).map(move |condition_idx| (dim, group_idx, condition_idx))
+        })
+        .collect()
+}
+
+fn condition_group_name(gmsh_para: &GmshPara, dim: u8, group_idx: usize) -> &str {
+    gmsh_para
+        .condition_groups()
+        .into_iter()
+        .find(|&(group_dim, idx, _)| group_dim == dim && idx == group_idx)
+        .map_or("", |(_, _, name)| name)
+}
+
+//"Surface inlet"
+fn group_label(dim: u8, name: &str) -> String {
+    let kind = match dim {
+        2 => "Surface",
+        1 => "Curve",
+        _ => "Point",
+    };
+    format!("{} {}", kind, name)
+}
+
+//"Fixed, Pressure"
+fn conditions_label(conditions: &[BoundaryCondition]) -> String {
+    let labels: Vec<&str> = conditions
+        .iter()
+        .map(|condition| condition.kind.label())
+        .collect();
+    labels.join(", ")
+}
+
+//groups for the group picker, with the conditions they already have
+fn condition_group_choices(gmsh_para: &GmshPara) -> Dropdown {
+    gmsh_para
+        .condition_groups()
+        .into_iter()
+        .map(|(dim, group_idx, name)| {
+            let conditions = gmsh_para
+                .group_conditions(dim, group_idx)
+                .map(|conditions| conditions_label(conditions))
+                .unwrap_or_default();
+            let meaning = if conditions.is_empty() {
+                String::from("no boundary condition")
+            } else {
+                conditions
+            };
+            (group_label(dim, name), meaning)
+        })
+        .collect()
+}
+
+fn row_convertion_condition(gmsh_para: &GmshPara, rows: &mut Vec<Row>) {
     rows.clear();
 
-    for surf_phy in surf_list.clone() {
-        let row = Row::new(vec![surf_phy.name, surf_phy.phys_id, surf_phy.surf_ids]);
-        rows.push(row);
+    for (dim, group_idx, condition_idx) in condition_rows(gmsh_para) {
+        let name = condition_group_name(gmsh_para, dim, group_idx);
+        let condition = &gmsh_para.group_conditions(dim, group_idx).unwrap()[condition_idx];
+        let row = Row::new(vec![
+            group_label(dim, name),
+            condition.kind.to_string(),
+            condition.summary(),
+        ]);
+        let valid = condition.check(gmsh_para.units.target).is_ok()
+            && condition
+                .kind
+                .check_dim(dim, gmsh_para.known_mesh_dimension())
+                .is_ok();
+        rows.push(if valid { row } else { row.red() });
     }
 
     rows.push(Row::new(["Enter", "To", "Add"]));
 }
 
-fn row_convertion_mesh(mesh_para: &MeshPara, rows: &mut Vec<Row>) {
+//a text box for one field parameter, on/off parameters are picked
+fn field_param_input(param: &FieldParam, value: &str) -> InputField {
+    let accept = match param.kind {
+        ParamKind::Surfaces | ParamKind::Curves | ParamKind::Points | ParamKind::FieldList => {
+            id_list_char
+        }
+        ParamKind::Number => float_char,
+        ParamKind::Integer | ParamKind::Field => digit_char,
+        ParamKind::Bool | ParamKind::Expression => any_char,
+    };
+
+    let input = InputField::new(param.name, value.to_string(), accept).fill();
+    match param.kind {
+        ParamKind::Bool => input.choices(to_dropdown(&[
+            ("", "Gmsh default"),
+            ("0", "off"),
+            ("1", "on"),
+        ])),
+        _ => input,
+    }
+}
+
+//catalog options matching the typed name, for the completion list
+fn option_suggestions(typed: &str) -> Dropdown {
+    complete_option(typed)
+        .into_iter()
+        .map(|option| (option.name.to_string(), option.description.to_string()))
+        .collect()
+}
+
+fn to_dropdown(entries: &[(&str, &str)]) -> Dropdown {
+    entries
+        .iter()
+        .map(|(value, meaning)| (value.to_string(), meaning.to_string()))
+        .collect()
+}
+
+//sizes are in the unit of the model, which is the target unit
+fn mesh_option_label(option: &GmshOption, unit: LengthUnit) -> String {
+    match option.kind {
+        OptionKind::Length => format!("{} ({})", option.short_name(), unit),
+        _ => option.short_name().to_string(),
+    }
+}
+
+fn row_convertion_mesh(mesh_para: &MeshPara, unit: LengthUnit, rows: &mut Vec<Row>) {
     rows.clear();
 
-    let tmp = mesh_para.clone();
+    for option in &MESH_OPTIONS {
+        let label = mesh_option_label(option, unit);
+        let value = mesh_para.get(option.name);
+        let row = if value.is_empty() {
+            let default = option.describe_value(option.default);
+            Row::new(vec![label, default]).dark_gray()
+        } else {
+            Row::new(vec![label, option.describe_value(value)])
+        };
+        rows.push(row);
+    }
+}
+
+fn row_convertion_export(export_settings: &[ExportSetting], rows: &mut Vec<Row>) {
+    rows.clear();
 
-    rows.push(Row::new(vec![String::from("MaxSize"), tmp.max_size]));
+    for setting in export_settings {
+        let enabled = if setting.enabled { "[x]" } else { "[ ]" };
+        rows.push(Row::new(vec![
+            enabled.to_string(),
+            setting.format.label().to_string(),
+            yes_no(setting.save_all),
+            setting.element_tag_type.to_string(),
+            String::from(if setting.nastran_deck { "y" } else { "" }),
+        ]));
+    }
+}
+
+//titles of all settings tables, the shown one highlighted
+fn settings_tabs_line(active: TypeMode) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (mode, title) in TypeMode::SETTINGS_TABS {
+        if !spans.is_empty() {
+            spans.push(Span::from("|"));
+        }
+        let span = Span::from(title);
+        spans.push(if mode == active {
+            span.reversed()
+        } else {
+            span
+        });
+    }
+    Line::from(spans)
 }
 
 fn popup_area(area: Rect, perc_x: u16, length_y: u16) -> Rect {